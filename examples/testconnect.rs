@@ -12,7 +12,7 @@ use std::fmt::Write;
 use anyhow::{bail, Result as AResult};
 use log::info;
 
-use monetdb::{parms::Parameters, Connection, Cursor};
+use monetdb::{parms::Parameters, Connection, Cursor, ReplyKind};
 
 const DEFAULT_QUERY: &str = r##"
 DROP TABLE IF EXISTS foo;
@@ -52,8 +52,9 @@ fn main() -> AResult<()> {
         println!("================================================================");
         cursor.execute(&query)?;
         loop {
-            if let Some(row_count) = cursor.affected_rows() {
-                if cursor.has_result_set() {
+            match cursor.reply_kind() {
+                ReplyKind::ResultSet => {
+                    let row_count = cursor.affected_rows().unwrap_or(0);
                     let md = cursor.column_metadata().to_vec();
                     let ncols = md.len();
                     println!("RESULT, {row_count} rows, {ncols} cols: {md:?}");
@@ -62,12 +63,11 @@ fn main() -> AResult<()> {
                     while cursor.next_row()? {
                         i += 1;
                         println!("  - ROW {i}/{row_count}:");
-                        for (i, col) in md.iter().enumerate() {
+                        for (col, value) in md.iter().zip(cursor.row_strings()?) {
                             let name = col.name();
                             let sql_type = col.sql_type();
                             buf.clear();
                             write!(buf, "{name} [{sql_type}]").unwrap();
-                            let value = cursor.get_str(i)?;
                             if let Some(s) = value {
                                 println!("      {buf:26} = {s}");
                             } else {
@@ -75,13 +75,12 @@ fn main() -> AResult<()> {
                             }
                         }
                     }
-                    // let rs = cursor.temporary_get_result_set()?.unwrap().trim_end();
-                    // println!("{rs}")
-                } else {
+                }
+                ReplyKind::Update => {
+                    let row_count = cursor.affected_rows().unwrap_or(0);
                     println!("OK, {row_count} affected rows");
                 }
-            } else {
-                println!("OK");
+                _ => println!("OK"),
             }
             if !cursor.next_reply()? {
                 break;
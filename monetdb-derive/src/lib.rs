@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+//! Derive macros for the [monetdb](https://crates.io/crates/monetdb) crate.
+//!
+//! Do not use this crate directly, enable the `derive` feature of the
+//! monetdb crate instead.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Implement `monetdb::FromRow` for a struct.
+///
+/// Fields of a struct with named fields are looked up by column name, which
+/// can be overridden with `#[monetdb(column = "...")]`. Fields of a tuple
+/// struct are taken from the columns in order.
+#[proc_macro_derive(FromRow, attributes(monetdb))]
+pub fn derive_from_row(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match from_row(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn from_row(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "FromRow can only be derived for structs",
+        ));
+    };
+
+    let body = match &data.fields {
+        Fields::Named(fields) => {
+            let mut inits = vec![];
+            for field in &fields.named {
+                let ident = field.ident.as_ref().unwrap();
+                let column = match column_attribute(&field.attrs)? {
+                    Some(lit) => lit.value(),
+                    None => ident.to_string().trim_start_matches("r#").to_string(),
+                };
+                inits.push(quote! {
+                    #ident: ::monetdb::convert::__derive::field(
+                        cursor,
+                        ::monetdb::convert::__derive::column(cursor, #column)?,
+                    )?
+                });
+            }
+            quote! { Self { #(#inits),* } }
+        }
+        Fields::Unnamed(fields) => {
+            let mut inits = vec![];
+            for (i, field) in fields.unnamed.iter().enumerate() {
+                if let Some(lit) = column_attribute(&field.attrs)? {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "fields of tuple structs are matched by position",
+                    ));
+                }
+                inits.push(quote! {
+                    ::monetdb::convert::__derive::field(cursor, #i)?
+                });
+            }
+            quote! { Self( #(#inits),* ) }
+        }
+        Fields::Unit => quote! { Self },
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::monetdb::FromRow for #name #ty_generics #where_clause {
+            fn from_row(cursor: &::monetdb::Cursor) -> ::monetdb::CursorResult<Self> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
+
+/// Extract the value of `#[monetdb(column = "...")]`, if present.
+fn column_attribute(attrs: &[syn::Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut column = None;
+    for attr in attrs {
+        if !attr.path().is_ident("monetdb") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("column") {
+                column = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown monetdb attribute"))
+            }
+        })?;
+    }
+    Ok(column)
+}
@@ -8,18 +8,25 @@
 
 use std::{
     collections::HashMap,
+    fmt,
+    ops::ControlFlow,
     sync::{
-        atomic::{self, AtomicBool},
-        Arc, Mutex, TryLockError,
+        atomic::{self, AtomicBool, AtomicU64, AtomicUsize},
+        Arc, Mutex, PoisonError, RwLock, TryLockError,
     },
+    thread,
+    time::Duration,
 };
 
 use crate::{
-    cursor::{delayed::DelayedCommands, Cursor, CursorError, CursorResult},
+    convert::{non_null, FromMonet},
+    cursor::{delayed::DelayedCommands, replies::ReplyParser, Cursor, CursorError, CursorResult},
     framing::{
-        connecting::{establish_connection, ConnectResult},
-        ServerSock, ServerState,
+        connecting::{establish_connection, ConnectError, ConnectResult, Endian},
+        reading::MapiReader,
+        reply_size_arg, Negotiated, ServerSock, ServerState, Traffic,
     },
+    observer::{QueryObserver, SharedQueryObserver},
     parms::Parameters,
 };
 
@@ -30,14 +37,128 @@ use crate::{
 ///
 /// Executing queries on a connection is done with a [`Cursor`] object, which
 /// can be obtained using the [`cursor()`](`Connection::cursor`) method.
+///
+/// A Connection is `Send` and `Sync`, so it can be shared between threads,
+/// for example in an [`Arc`]. Each thread should create its own cursors.
+/// All communication with the server goes through an internal mutex, so
+/// cursors on the same connection take turns: a thread that executes a query
+/// or fetches rows while another thread is doing so waits until the other is
+/// done. Use a separate connection per thread to run queries in parallel.
+///
+/// Taking turns happens per request, not per result set. A cursor can stop
+/// halfway through a result set while other cursors execute queries, the
+/// remaining rows stay on the server until the cursor fetches them.
 pub struct Connection(Arc<Conn>);
 
 pub(crate) struct Conn {
-    pub(crate) reply_size: usize,
+    pub(crate) reply_size: AtomicUsize,
+    pub(crate) slow_query_log: Option<Duration>,
+    pub(crate) max_prefetch: Option<usize>,
+    pub(crate) max_result_bytes: Option<usize>,
+    server_endian: Endian,
+    negotiated: Negotiated,
+    initial_autocommit: bool,
+    welcome_message: Option<String>,
+    observer: RwLock<Option<SharedQueryObserver>>,
+    pub(crate) statements: AtomicU64,
+    pub(crate) rows_fetched: AtomicU64,
+    traffic: Arc<Traffic>,
     locked: Mutex<Locked>,
     closing: AtomicBool,
 }
 
+/// Counters kept by a [`Connection`], see [`Connection::stats()`].
+///
+/// The counters are per connection, start at zero when the connection is
+/// established and only ever increase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConnStats {
+    /// The number of times [`Cursor::execute()`] or one of its variants sent
+    /// statements to the server. One call may contain several statements.
+    pub statements: u64,
+    /// The number of result set rows the cursors have moved to, or skipped
+    /// over using [`Cursor::skip_rows()`].
+    pub rows_fetched: u64,
+    /// The number of bytes sent to the server, including the login.
+    pub bytes_sent: u64,
+    /// The number of bytes received from the server, including the login.
+    pub bytes_received: u64,
+}
+
+/// A snapshot of the state of a [`Connection`], see
+/// [`Connection::diagnostics()`].
+///
+/// The [`Display`](`fmt::Display`) implementation renders it as a block of
+/// text suitable for pasting into a bug report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostics {
+    /// The MAPI protocol version, currently always 9.
+    pub protocol_version: u8,
+    /// The byte order announced by the server.
+    pub server_endian: Endian,
+    /// The algorithm used to hash the password during the login.
+    pub hash_algo: &'static str,
+    /// The algorithm the server uses to store passwords.
+    pub prehash_algo: &'static str,
+    /// The binary result set protocol level offered by the server. This
+    /// crate does not use it yet.
+    pub binary_level: u16,
+    /// The current default reply size, 0 means unlimited.
+    pub reply_size: usize,
+    /// The autocommit mode established when connecting. Later changes made
+    /// with SQL statements such as `START TRANSACTION` are not reflected.
+    pub initial_autocommit: bool,
+    /// The server version, if the [server metadata](`Connection::metadata`)
+    /// has been retrieved.
+    pub server_version: Option<(u16, u16, u16)>,
+    /// The session id, if [`Connection::session_id()`] has been called.
+    pub session_id: Option<i64>,
+    /// Whether the connection has been closed.
+    pub closed: bool,
+    /// The counters also returned by [`Connection::stats()`].
+    pub stats: ConnStats,
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |b: bool| if b { "on" } else { "off" };
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        writeln!(f, "protocol version:   {}", self.protocol_version)?;
+        writeln!(f, "server endian:      {}", self.server_endian)?;
+        writeln!(
+            f,
+            "password hash:      {} (prehash {})",
+            self.hash_algo, self.prehash_algo
+        )?;
+        writeln!(f, "binary level:       {}", self.binary_level)?;
+        writeln!(f, "reply size:         {}", self.reply_size)?;
+        writeln!(f, "initial autocommit: {}", on_off(self.initial_autocommit))?;
+        match self.server_version {
+            Some((major, minor, patch)) => {
+                writeln!(f, "server version:     {major}.{minor}.{patch}")?
+            }
+            None => writeln!(f, "server version:     unknown")?,
+        }
+        match self.session_id {
+            Some(id) => writeln!(f, "session id:         {id}")?,
+            None => writeln!(f, "session id:         unknown")?,
+        }
+        writeln!(f, "closed:             {}", yes_no(self.closed))?;
+        let ConnStats {
+            statements,
+            rows_fetched,
+            bytes_sent,
+            bytes_received,
+        } = self.stats;
+        writeln!(f, "statements:         {statements}")?;
+        writeln!(f, "rows fetched:       {rows_fetched}")?;
+        writeln!(f, "bytes sent:         {bytes_sent}")?;
+        writeln!(f, "bytes received:     {bytes_received}")
+    }
+}
+
 struct Locked {
     state: ServerState,
     sock: Option<ServerSock>,
@@ -47,9 +168,17 @@ struct Locked {
 impl Connection {
     /// Create a new connection based on the given [`Parameters`] object.
     pub fn new(parameters: Parameters) -> ConnectResult<Connection> {
-        let (sock, state, delayed) = establish_connection(parameters)?;
+        let (sock, state, negotiated, delayed) = establish_connection(parameters)?;
 
         let reply_size = state.reply_size;
+        let slow_query_log = state.slow_query_log;
+        let max_prefetch = state.max_prefetch;
+        let max_result_bytes = state.max_result_bytes;
+        let fetch_metadata = state.fetch_metadata;
+        let server_endian = state.server_endian;
+        let initial_autocommit = state.initial_auto_commit;
+        let welcome_message = state.welcome_message.clone();
+        let traffic = sock.traffic();
 
         let locked = Locked {
             state,
@@ -59,13 +188,59 @@ impl Connection {
         let conn = Conn {
             locked: Mutex::new(locked),
             closing: AtomicBool::new(false),
-            reply_size,
+            reply_size: AtomicUsize::new(reply_size),
+            slow_query_log,
+            max_prefetch,
+            max_result_bytes,
+            server_endian,
+            negotiated,
+            initial_autocommit,
+            welcome_message,
+            observer: RwLock::new(None),
+            statements: AtomicU64::new(0),
+            rows_fetched: AtomicU64::new(0),
+            traffic,
         };
-        let connection = Connection(Arc::new(conn));
+        let mut connection = Connection(Arc::new(conn));
+
+        if fetch_metadata {
+            connection
+                .refresh_metadata()
+                .map_err(ConnectError::Metadata)?;
+        }
 
         Ok(connection)
     }
 
+    /// Like [`Connection::new()`] but if connecting fails with an error that
+    /// is [retryable](`ConnectError::is_retryable`), wait and try again, up to
+    /// `attempts` attempts in total. The first wait is `base_delay` and every
+    /// following wait is twice as long as the previous one.
+    ///
+    /// Other errors, for example a rejected login, are returned immediately.
+    /// When all attempts fail, the error of the last attempt is returned.
+    /// This is useful when the application and the database are started at
+    /// the same time, for example in containers.
+    pub fn connect_with_retry(
+        parameters: Parameters,
+        attempts: u32,
+        base_delay: Duration,
+    ) -> ConnectResult<Connection> {
+        let mut delay = base_delay;
+        let mut attempt = 1;
+        loop {
+            match Self::new(parameters.clone()) {
+                Err(e) if e.is_retryable() && attempt < attempts => {
+                    debug!("connection attempt {attempt} failed, retrying in {delay:?}: {e}");
+                    thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Create a new connection based on the given URL.
     pub fn connect_url(url: &str) -> ConnectResult<Connection> {
         let parms = Parameters::from_url(url)?;
@@ -77,11 +252,308 @@ impl Connection {
         Cursor::new(Arc::clone(&self.0))
     }
 
+    /// Change the number of rows the server sends in the initial response to a
+    /// query, and the number of rows cursors fetch at a time after that.
+    ///
+    /// This affects all queries executed on this connection after the call.
+    /// Cursors that already exist keep fetching in chunks of the old size,
+    /// cursors created afterwards use the new size.
+    ///
+    /// If `n` is 0 the server sends all rows in the initial response and no
+    /// further fetches are needed.
+    pub fn set_default_reply_size(&self, n: usize) -> CursorResult<()> {
+        let cmd = format!("Xreply_size {}", reply_size_arg(n));
+        let mut vec = vec![];
+        let mut delayed_result = Ok(());
+        self.0.run_locked(|state, delayed, mut sock| {
+            sock = delayed.send_delayed_plus(sock, &[cmd.as_bytes()])?;
+            (sock, delayed_result) = delayed.recv_delayed(sock, &mut vec)?;
+            sock = MapiReader::to_end(sock, &mut vec)?;
+            if ReplyParser::detect_errors(&vec).is_ok() {
+                state.reply_size = n;
+            }
+            Ok(sock)
+        })?;
+        ReplyParser::detect_errors(&vec)?;
+        self.0.reply_size.store(n, atomic::Ordering::SeqCst);
+        delayed_result
+    }
+
+    /// Return the current schema of the session, as reported by the server.
+    pub fn current_schema(&self) -> CursorResult<String> {
+        self.query_string("SELECT current_schema")
+    }
+
+    /// Return the name of the user the session is logged in as, as reported by
+    /// the server.
+    pub fn current_user(&self) -> CursorResult<String> {
+        self.query_string("SELECT current_user")
+    }
+
+    /// Return the id of this connection's session on the server, as listed
+    /// in the `sessionid` column of `sys.sessions`.
+    ///
+    /// The id is retrieved from the server on the first call and cached
+    /// afterwards.
+    pub fn session_id(&self) -> CursorResult<i64> {
+        let mut cached = None;
+        self.0.run_locked(|state, _delayed, sock| {
+            cached = state.session_id;
+            Ok(sock)
+        })?;
+        if let Some(id) = cached {
+            return Ok(id);
+        }
+
+        let mut cursor = self.cursor();
+        cursor.execute("SELECT sys.current_sessionid()")?;
+        if !cursor.next_row()? {
+            return Err(CursorError::NoResultSet);
+        }
+        let Some(id) = cursor.get_i64(0)? else {
+            return Err(CursorError::Metadata("current_sessionid() returned NULL"));
+        };
+        cursor.close()?;
+
+        self.0.run_locked(|state, _delayed, sock| {
+            state.session_id = Some(id);
+            Ok(sock)
+        })?;
+        Ok(id)
+    }
+
+    /// Send `bytes` to the server as a single MAPI message and return the
+    /// complete reply, without interpreting it. Error replies are returned
+    /// as-is rather than as [`CursorError::Server`].
+    ///
+    /// The message is sent verbatim, so SQL statements must carry the `s`
+    /// prefix and the trailing `;` themselves, and MAPI commands the `X`
+    /// prefix. This is meant for testing and for experimenting with server
+    /// features the driver does not support yet. Commands that change the
+    /// state of the session, such as `Xreply_size`, can confuse the driver.
+    pub fn raw_command(&self, bytes: &[u8]) -> CursorResult<Vec<u8>> {
+        let mut vec = vec![];
+        let mut delayed_result = Ok(());
+        self.0.run_locked(|_state, delayed, mut sock| {
+            sock = delayed.send_delayed_plus(sock, &[bytes])?;
+            (sock, delayed_result) = delayed.recv_delayed(sock, &mut vec)?;
+            sock = MapiReader::to_end(sock, &mut vec)?;
+            Ok(sock)
+        })?;
+        delayed_result?;
+        Ok(vec)
+    }
+
+    /// Execute a query that returns at most one row and return the value in
+    /// its first column, or `None` if it returns no rows. More than one row
+    /// is an error, [`CursorError::TooManyRows`].
+    ///
+    /// A NULL value is an error too unless `T` is an `Option`, see
+    /// [`FromMonet`]. The result set is released before returning.
+    pub fn query_opt<T: FromMonet>(&self, sql: &str) -> CursorResult<Option<T>> {
+        let mut cursor = self.cursor();
+        cursor.execute(sql)?;
+        if !cursor.next_row()? {
+            cursor.close()?;
+            return Ok(None);
+        }
+        let value = non_null(cursor.get::<T>(0)?, 0)?;
+        let more = cursor.next_row()?;
+        cursor.close()?;
+        if more {
+            return Err(CursorError::TooManyRows);
+        }
+        Ok(Some(value))
+    }
+
+    /// Execute a query that returns a single string value.
+    fn query_string(&self, query: &str) -> CursorResult<String> {
+        let mut cursor = self.cursor();
+        cursor.execute(query)?;
+        if !cursor.next_row()? {
+            return Err(CursorError::NoResultSet);
+        }
+        let value = cursor.get_str(0)?.unwrap_or("").to_string();
+        cursor.close()?;
+        Ok(value)
+    }
+
+    /// Install a callback that is invoked with a
+    /// [`QueryEvent`](`crate::QueryEvent`) after the server has replied to
+    /// statements executed on this connection, and again when a cursor
+    /// reaches the end of each result set.
+    ///
+    /// The callback runs on the thread that uses the cursor, so it should
+    /// return quickly. Cursors pick up the observer when executing
+    /// statements, statements that have already been executed are not
+    /// affected.
+    pub fn set_query_observer(&self, observer: QueryObserver) {
+        *self
+            .0
+            .observer
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Arc::from(observer));
+    }
+
+    /// Remove the callback installed with
+    /// [`set_query_observer()`](`Connection::set_query_observer`).
+    pub fn clear_query_observer(&self) {
+        *self
+            .0
+            .observer
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Return the statement, row and byte counters of this connection.
+    ///
+    /// This is a cheap alternative to
+    /// [`set_query_observer()`](`Connection::set_query_observer`) for basic
+    /// monitoring. It does not block, not even while another thread is using
+    /// the connection. The byte counts include the MAPI framing but not the
+    /// TLS overhead.
+    pub fn stats(&self) -> ConnStats {
+        let load = |counter: &AtomicU64| counter.load(atomic::Ordering::Relaxed);
+        ConnStats {
+            statements: load(&self.0.statements),
+            rows_fetched: load(&self.0.rows_fetched),
+            bytes_sent: load(&self.0.traffic.sent),
+            bytes_received: load(&self.0.traffic.received),
+        }
+    }
+
+    /// Run `f` on a new cursor that returns [`CursorError::Busy`] instead of
+    /// waiting when another thread is using the connection, for example to
+    /// keep a user interface responsive while a background query runs.
+    ///
+    /// A `Busy` error leaves the cursor intact, so `f` may handle it and try
+    /// again later. Moving past a result set the server has not sent
+    /// completely still waits for the connection, because the request to
+    /// release it must be queued.
+    pub fn try_cursor_op<T>(
+        &self,
+        f: impl FnOnce(&mut Cursor) -> CursorResult<T>,
+    ) -> CursorResult<T> {
+        let mut cursor = self.cursor();
+        cursor.set_try_lock(true);
+        f(&mut cursor)
+    }
+
+    /// Execute `sql` every `interval` and pass the cursor, positioned at the
+    /// first reply, to `f`, until `f` returns [`ControlFlow::Break`]. Returns
+    /// the value `f` breaks with, or the first error.
+    ///
+    /// MonetDB has no equivalent of PostgreSQL's `LISTEN`/`NOTIFY`: the MAPI
+    /// protocol has no channel for the server to send messages the client
+    /// did not ask for. Polling a table or view that records the events of
+    /// interest is the supported alternative, and this method takes care of
+    /// the loop. The interval is the pause between the end of one poll and
+    /// the start of the next. The connection is not used while waiting, so
+    /// other threads can use it in the meantime.
+    ///
+    /// ```no_run
+    /// # use std::{ops::ControlFlow, time::Duration};
+    /// # use monetdb::Connection;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::connect_url("monetdb:///demo")?;
+    /// let mut last_seen = 0;
+    /// let sql = "SELECT MAX(id) FROM events";
+    /// conn.poll_query(sql, Duration::from_secs(1), |cursor| {
+    ///     cursor.next_row()?;
+    ///     let id = cursor.get_i64(0)?.unwrap_or(0);
+    ///     if id > last_seen {
+    ///         println!("new events up to {id}");
+    ///         last_seen = id;
+    ///     }
+    ///     Ok(ControlFlow::<()>::Continue(()))
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn poll_query<T>(
+        &self,
+        sql: &str,
+        interval: Duration,
+        mut f: impl FnMut(&mut Cursor) -> CursorResult<ControlFlow<T>>,
+    ) -> CursorResult<T> {
+        let mut cursor = self.cursor();
+        loop {
+            cursor.execute(sql)?;
+            if let ControlFlow::Break(value) = f(&mut cursor)? {
+                return Ok(value);
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    /// Return the byte order the server announced when the connection was
+    /// established.
+    pub fn server_endian(&self) -> Endian {
+        self.0.server_endian
+    }
+
+    /// Return a snapshot of the negotiated protocol settings, the server
+    /// version and the counters of this connection, for example to include
+    /// in a bug report.
+    ///
+    /// Like [`stats()`](`Connection::stats`) this does not contact the server
+    /// and does not block. The server version and session id are only
+    /// included if they have been retrieved before and no other thread is
+    /// using the connection at that moment.
+    pub fn diagnostics(&self) -> Diagnostics {
+        let conn = self.0.as_ref();
+        let locked = match conn.locked.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        };
+        let state = locked.as_ref().map(|guard| &guard.state);
+        let server_version = state
+            .and_then(|s| s.sql_metadata.as_ref())
+            .map(|md| md.version);
+        let session_id = state.and_then(|s| s.session_id);
+        drop(locked);
+        Diagnostics {
+            protocol_version: conn.negotiated.protocol,
+            server_endian: conn.server_endian,
+            hash_algo: conn.negotiated.hash_algo,
+            prehash_algo: conn.negotiated.prehash_algo,
+            binary_level: conn.negotiated.binary_level,
+            reply_size: conn.reply_size.load(atomic::Ordering::SeqCst),
+            initial_autocommit: conn.initial_autocommit,
+            server_version,
+            session_id,
+            closed: conn.is_closed(),
+            stats: self.stats(),
+        }
+    }
+
+    /// Return the message the server sent after a successful login, if any.
+    ///
+    /// Servers do not normally send one but some deployments use it for
+    /// information such as the build or release. Multi-line messages are
+    /// returned with the leading `#` of each line removed.
+    pub fn welcome_message(&self) -> Option<&str> {
+        self.0.welcome_message.as_deref()
+    }
+
+    /// Return `true` if the connection has been closed, for example because
+    /// of an IO error. Does not contact the server and does not block.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
     /// Close the connection.
     ///
     /// Any remaining cursors will not be able to fetch new data.
     /// They may still be able to return some already retrieved data but
     /// you shouldn't count on that.
+    ///
+    /// Commands that are still queued, such as requests to release result
+    /// sets on the server, are sent before the socket is closed. This is
+    /// best effort: the responses are not awaited, write errors are ignored
+    /// and if another thread is using the connection at that moment nothing
+    /// is sent. Dropping the connection does the same.
     pub fn close(self) {
         drop(self);
     }
@@ -90,12 +562,28 @@ impl Connection {
         let conn = self.0.as_ref();
         conn.closing.store(true, atomic::Ordering::SeqCst);
         match conn.locked.try_lock() {
-            Ok(mut locked) => locked.sock = None,
+            Ok(mut locked) => {
+                let Locked { sock, delayed, .. } = &mut *locked;
+                if let Some(sock) = sock.take() {
+                    // Best effort: send any queued commands such as Xclose
+                    // so the server can release the result sets, but do not
+                    // wait for the responses.
+                    if !delayed.responses.is_empty() {
+                        let _ = delayed.send_delayed(sock);
+                    }
+                }
+            }
             Err(TryLockError::Poisoned(mut poisoned)) => poisoned.get_mut().sock = None,
             Err(TryLockError::WouldBlock) => {}
         }
     }
 
+    /// Return information about the server.
+    ///
+    /// The information is retrieved on the first call and cached afterwards,
+    /// so this returns a snapshot. Use
+    /// [`refresh_metadata()`](`Connection::refresh_metadata`) to retrieve it
+    /// again.
     pub fn metadata(&mut self) -> CursorResult<ServerMetadata> {
         let mut inner = None;
         self.0.run_locked(|state, _delayed, sock| {
@@ -105,7 +593,16 @@ impl Connection {
         if let Some(md) = inner {
             return Ok(ServerMetadata(md));
         }
+        self.refresh_metadata()
+    }
 
+    /// Retrieve the information returned by
+    /// [`metadata()`](`Connection::metadata`) from the server again and
+    /// replace the cached copy.
+    ///
+    /// Useful on long-lived connections where for example environment
+    /// settings may have changed.
+    pub fn refresh_metadata(&mut self) -> CursorResult<ServerMetadata> {
         // create it and put it in the state
         // (ignore harmless race condition)
         let new_metadata = ServerMetadata::new(self)?;
@@ -117,6 +614,7 @@ impl Connection {
     }
 }
 
+/// See [`Connection::close`].
 impl Drop for Connection {
     fn drop(&mut self) {
         self.close_connection();
@@ -124,6 +622,27 @@ impl Drop for Connection {
 }
 
 impl Conn {
+    /// Check whether the connection has been closed, without blocking.
+    /// If another thread is currently using the connection it is clearly
+    /// not closed.
+    pub(crate) fn is_closed(&self) -> bool {
+        if self.closing.load(atomic::Ordering::SeqCst) {
+            return true;
+        }
+        match self.locked.try_lock() {
+            Ok(locked) => locked.sock.is_none(),
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.get_ref().sock.is_none(),
+            Err(TryLockError::WouldBlock) => false,
+        }
+    }
+
+    pub(crate) fn query_observer(&self) -> Option<SharedQueryObserver> {
+        self.observer
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
     pub(crate) fn run_locked<F>(&self, f: F) -> CursorResult<()>
     where
         F: for<'x> FnOnce(
@@ -132,7 +651,30 @@ impl Conn {
             ServerSock,
         ) -> CursorResult<ServerSock>,
     {
-        let mut guard = self.locked.lock().unwrap();
+        self.run_locked_with(true, f)
+    }
+
+    /// Like [`run_locked()`](`Conn::run_locked`) but if `block` is false,
+    /// return [`CursorError::Busy`] instead of waiting for the lock.
+    pub(crate) fn run_locked_with<F>(&self, block: bool, f: F) -> CursorResult<()>
+    where
+        F: for<'x> FnOnce(
+            &'x mut ServerState,
+            &'x mut DelayedCommands,
+            ServerSock,
+        ) -> CursorResult<ServerSock>,
+    {
+        // The socket is taken out of the mutex while it is in use, so after a
+        // panic the recovered guard simply holds a closed connection.
+        let mut guard = if block {
+            self.locked.lock().unwrap_or_else(PoisonError::into_inner)
+        } else {
+            match self.locked.try_lock() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => return Err(CursorError::Busy),
+            }
+        };
         let Some(sock) = guard.sock.take() else {
             return Err(CursorError::Closed);
         };
@@ -161,14 +703,11 @@ impl ServerMetadata {
     fn new(conn: &mut Connection) -> CursorResult<Self> {
         let mut cursor = conn.cursor();
         cursor.execute("SELECT name, value FROM sys.environment")?;
-        let mut environment = HashMap::new();
-        while cursor.next_row()? {
-            let name = cursor
-                .get_str(0)?
-                .expect("sys.environment.name should not be null");
-            let value = cursor.get_str(1)?.unwrap_or("");
-            environment.insert(name.to_string(), value.to_string());
-        }
+        let environment: HashMap<String, String> = cursor
+            .collect_map::<String, Option<String>>(0, 1)?
+            .into_iter()
+            .map(|(name, value)| (name, value.unwrap_or_default()))
+            .collect();
 
         // parse version
         let Some(v) = environment.get("monet_version") else {
@@ -196,16 +735,10 @@ impl ServerMetadata {
         }
         let version = (major, minor, patch);
 
-        let mut prehash_algo: &'static str = "";
-        conn.0.run_locked(|state, _delayed, sock| {
-            prehash_algo = state.prehash_algo;
-            Ok(sock)
-        })?;
-
         let inner = InnerServerMetadata {
             environment,
             version,
-            prehash_algo,
+            prehash_algo: conn.0.negotiated.prehash_algo,
         };
         let metadata = ServerMetadata(Arc::new(inner));
         Ok(metadata)
@@ -215,6 +748,29 @@ impl ServerMetadata {
         self.0.environment.get(key).map(String::as_ref)
     }
 
+    /// The maximum number of concurrent sessions the server allows, from
+    /// the `max_clients` environment setting.
+    pub fn max_clients(&self) -> Option<u32> {
+        self.env("max_clients")?.parse().ok()
+    }
+
+    /// The location of the database on the server, from the `gdk_dbpath`
+    /// environment setting.
+    pub fn gdk_dbpath(&self) -> Option<&str> {
+        self.env("gdk_dbpath")
+    }
+
+    /// The name of the MonetDB release, for example `Aug2024-SP1`, from the
+    /// `monet_release` environment setting.
+    pub fn monet_release(&self) -> Option<&str> {
+        self.env("monet_release")
+    }
+
+    /// The `raw_strings_nil` environment setting.
+    pub fn raw_strings_nil(&self) -> Option<&str> {
+        self.env("raw_strings_nil")
+    }
+
     pub fn version(&self) -> (u16, u16, u16) {
         self.0.version
     }
@@ -223,3 +779,15 @@ impl ServerMetadata {
         self.0.prehash_algo
     }
 }
+
+#[test]
+fn test_send_sync() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    assert_send::<Connection>();
+    assert_sync::<Connection>();
+    assert_send::<Cursor>();
+    assert_send::<ServerMetadata>();
+    assert_sync::<ServerMetadata>();
+}
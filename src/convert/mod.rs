@@ -23,35 +23,168 @@ macro_rules! fromstr_frommonet {
 }
 
 pub mod raw_decimal;
+pub mod raw_inet;
 pub mod raw_temporal;
 
 #[cfg(feature = "time")]
 pub mod temporal_time;
 
+/// Return the offset of the time zone of this machine in seconds east of UTC.
+/// This is what is sent to the server if no time zone has been configured,
+/// and what `timezone=local` resolves to.
+pub(crate) fn local_timezone_seconds() -> i32 {
+    // If a date/time crate has been activated, use that.
+    // Otherwise, return UTC.
+    let implementations = [
+        #[cfg(feature = "time")]
+        temporal_time::timezone_offset_east_of_utc,
+        // Fallback
+        || 0i32,
+    ];
+    (implementations[0])()
+}
+
 #[cfg(test)]
 mod tests;
 
 use std::{
     any::{type_name, Any},
-    fmt,
+    error, fmt,
+    net::IpAddr,
     str::FromStr,
 };
 
 use raw_decimal::RawDecimal;
+use raw_inet::RawInet;
+
+pub use crate::cursor::replies::{FieldBytes, ResultSet};
 
 use crate::{
-    cursor::replies::{BadReply, ResultSet},
-    CursorError, CursorResult,
+    cursor::replies::BadReply, util::errorsource::ErrorSource, Cursor, CursorError, CursorResult,
+    MonetType,
 };
 
 /// A type that can be extracted from a result set.
+///
+/// This trait can also be implemented outside this crate, using
+/// [`ResultSet::field()`] to access the data and [`conversion_error()`] to
+/// report invalid values:
+///
+/// ```
+/// use monetdb::convert::{conversion_error, FromMonet, ResultSet};
+/// use monetdb::{CursorResult, MonetType};
+///
+/// /// A point stored in a VARCHAR column as 'x,y'.
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// impl FromMonet for Point {
+///     fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+///         let Some(field) = rs.field(colnr) else {
+///             return Ok(None);
+///         };
+///         if !matches!(field.sql_type(), MonetType::Varchar(_)) {
+///             return Err(conversion_error::<Self>("expected a VARCHAR column"));
+///         }
+///         let Some((x, y)) = field.as_str()?.split_once(',') else {
+///             return Err(conversion_error::<Self>("expected 'x,y'"));
+///         };
+///         let parse = |s: &str| s.trim().parse().map_err(conversion_error::<Self>);
+///         Ok(Some(Point { x: parse(x)?, y: parse(y)? }))
+///     }
+/// }
+/// ```
 pub trait FromMonet
 where
     Self: Sized,
 {
+    /// Extract column `colnr` of the current row, returning `None` if it is
+    /// NULL.
     fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>>;
 }
 
+/// Types that can be constructed from the current row of a [`Cursor`].
+///
+/// With the `derive` feature enabled this trait can be derived for structs
+/// using `#[derive(FromRow)]`. Named fields are looked up by column name, see
+/// [`Cursor::column_index`], and a different column name can be given with
+/// `#[monetdb(column = "...")]`. The fields of tuple structs are taken from
+/// the columns in order. Each field is extracted using [`FromMonet`], NULL
+/// values are only allowed in fields of type `Option<T>`.
+///
+/// It is also implemented for tuples of up to 12 elements, see
+/// [`Cursor::get_row()`].
+pub trait FromRow
+where
+    Self: Sized,
+{
+    fn from_row(cursor: &Cursor) -> CursorResult<Self>;
+}
+
+macro_rules! tuple_fromrow {
+    ($arity:literal: $($type:ident $idx:tt),+) => {
+        /// Extracts the columns in order, the number of columns must match.
+        /// NULL values are only allowed for elements of type `Option<T>`.
+        impl<$($type: FromMonet),+> FromRow for ($($type,)+) {
+            fn from_row(cursor: &Cursor) -> CursorResult<Self> {
+                let ncols = cursor.result_set()?.columns().len();
+                if ncols != $arity {
+                    return Err(CursorError::ColumnCount {
+                        expected: $arity,
+                        actual: ncols,
+                    });
+                }
+                Ok(($(non_null(cursor.get::<$type>($idx)?, $idx)?,)+))
+            }
+        }
+    };
+}
+
+tuple_fromrow!(1: A 0);
+tuple_fromrow!(2: A 0, B 1);
+tuple_fromrow!(3: A 0, B 1, C 2);
+tuple_fromrow!(4: A 0, B 1, C 2, D 3);
+tuple_fromrow!(5: A 0, B 1, C 2, D 3, E 4);
+tuple_fromrow!(6: A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_fromrow!(7: A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_fromrow!(8: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+tuple_fromrow!(9: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+tuple_fromrow!(10: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+tuple_fromrow!(11: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+tuple_fromrow!(12: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+/// Support functions for the code generated by `#[derive(FromRow)]`.
+#[doc(hidden)]
+pub mod __derive {
+    use crate::{Cursor, CursorError, CursorResult};
+
+    use super::FromMonet;
+
+    pub fn column(cursor: &Cursor, name: &str) -> CursorResult<usize> {
+        cursor
+            .column_index(name)
+            .ok_or_else(|| CursorError::UnknownColumn(name.to_string()))
+    }
+
+    pub fn field<T: FromMonet>(cursor: &Cursor, colnr: usize) -> CursorResult<T> {
+        super::non_null(cursor.get(colnr)?, colnr)
+    }
+}
+
+/// Turn the `None` returned by [`FromMonet::extract`] for NULL into an error.
+pub(crate) fn non_null<T>(value: Option<T>, colnr: usize) -> CursorResult<T> {
+    match value {
+        Some(value) => Ok(value),
+        None => Err(CursorError::Conversion {
+            expected_type: type_name::<T>(),
+            message: format!("column {colnr} is NULL").into(),
+            source: None,
+        }),
+    }
+}
+
 fromstr_frommonet!(bool);
 fromstr_frommonet!(i8);
 fromstr_frommonet!(u8);
@@ -68,6 +201,9 @@ fromstr_frommonet!(usize);
 fromstr_frommonet!(f32);
 fromstr_frommonet!(f64);
 
+fromstr_frommonet!(IpAddr);
+fromstr_frommonet!(RawInet);
+
 fromstr_frommonet!(RawDecimal<i8>);
 fromstr_frommonet!(RawDecimal<u8>);
 fromstr_frommonet!(RawDecimal<i16>);
@@ -79,6 +215,45 @@ fromstr_frommonet!(RawDecimal<u64>);
 fromstr_frommonet!(RawDecimal<i128>);
 fromstr_frommonet!(RawDecimal<u128>);
 
+/// Nullable values.
+///
+/// `extract` already uses `None` to indicate NULL, so this impl never returns
+/// `Ok(None)`: NULL becomes `Ok(Some(None))` and any other value `v` becomes
+/// `Ok(Some(Some(v)))`. This is useful where a `FromMonet` type is required
+/// that can also hold NULL, for example a field of type `Option<i32>` in a
+/// struct whose other fields must not be NULL.
+impl<T: FromMonet> FromMonet for Option<T> {
+    fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+        Ok(Some(T::extract(rs, colnr)?))
+    }
+}
+
+/// Any type, as text
+impl FromMonet for String {
+    fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+        let Some(field) = rs.row_set.get_field_raw(colnr) else {
+            return Ok(None);
+        };
+        let s = from_utf8(field)?;
+        Ok(Some(s.to_string()))
+    }
+}
+
+/// Any type whose text is a single character, typically CHAR(1)
+impl FromMonet for char {
+    fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+        let Some(field) = rs.row_set.get_field_raw(colnr) else {
+            return Ok(None);
+        };
+        let mut chars = from_utf8(field)?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Some(c)),
+            (None, _) => Err(conversion_error::<Self>("empty string")),
+            (Some(_), Some(_)) => Err(conversion_error::<Self>("more than one character")),
+        }
+    }
+}
+
 /// BLOB
 impl FromMonet for Vec<u8> {
     fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
@@ -87,7 +262,7 @@ impl FromMonet for Vec<u8> {
         };
         match hex::decode(field) {
             Ok(vec) => Ok(Some(vec)),
-            Err(e) => Err(conversion_error::<Self>(e)),
+            Err(e) => Err(conversion_error_from::<Self>(e)),
         }
     }
 }
@@ -101,7 +276,7 @@ impl FromMonet for uuid::Uuid {
         };
         match uuid::Uuid::try_parse_ascii(field) {
             Ok(u) => Ok(Some(u)),
-            Err(e) => Err(conversion_error::<Self>(e)),
+            Err(e) => Err(conversion_error_from::<Self>(e)),
         }
     }
 }
@@ -124,7 +299,126 @@ impl FromMonet for decimal_rs::Decimal {
         let Some(field) = rs.row_set.get_field_raw(colnr) else {
             return Ok(None);
         };
-        transform(field, decimal_rs::Decimal::from_str)
+        // DecimalParseError does not implement std::error::Error
+        transform_display(field, decimal_rs::Decimal::from_str)
+    }
+}
+
+/// A boolean that may also be stored as a number, for schemas that model
+/// booleans as for example `TINYINT` 0/1.
+///
+/// BOOLEAN columns are extracted as for `bool`. For the integer types zero
+/// becomes `false` and any other value `true`. Other column types are a
+/// conversion error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct IntBool(pub bool);
+
+impl From<IntBool> for bool {
+    fn from(value: IntBool) -> bool {
+        value.0
+    }
+}
+
+impl FromMonet for IntBool {
+    fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+        use MonetType::*;
+        let Some(field) = rs.row_set.get_field_raw(colnr) else {
+            return Ok(None);
+        };
+        let value = match rs.columns[colnr].sql_type() {
+            Bool => transform_fromstr::<bool>(field)?,
+            TinyInt | SmallInt | Int | BigInt | HugeInt => {
+                transform_fromstr::<i128>(field)?.map(|i| i != 0)
+            }
+            other => {
+                return Err(conversion_error::<Self>(format!(
+                    "expected BOOLEAN or integer column, found {other}"
+                )))
+            }
+        };
+        Ok(value.map(IntBool))
+    }
+}
+
+/// An exact decimal value `ScaledDecimal(n, s)`, to be interpreted as
+/// `n * 10^(-s)`, extracted from a DECIMAL column.
+///
+/// Unlike [`RawDecimal<i128>`], the value is checked against the declared
+/// type of the column: the scale must be the scale of the column and the
+/// number of digits must not exceed its precision. Any other column type is
+/// a conversion error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScaledDecimal(pub i128, pub u8);
+
+impl From<ScaledDecimal> for (i128, u8) {
+    fn from(value: ScaledDecimal) -> (i128, u8) {
+        (value.0, value.1)
+    }
+}
+
+impl FromMonet for ScaledDecimal {
+    fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+        let Some(field) = rs.row_set.get_field_raw(colnr) else {
+            return Ok(None);
+        };
+        let MonetType::Decimal(precision, scale) = *rs.columns[colnr].sql_type() else {
+            let other = rs.columns[colnr].sql_type();
+            return Err(conversion_error::<Self>(format!(
+                "expected DECIMAL column, found {other}"
+            )));
+        };
+        let Some(RawDecimal(value, found_scale)) = transform_fromstr::<RawDecimal<i128>>(field)?
+        else {
+            unreachable!("transform never returns Ok(None)")
+        };
+        if found_scale != scale {
+            return Err(conversion_error::<Self>(format!(
+                "DECIMAL({precision}, {scale}) value has {found_scale} decimals"
+            )));
+        }
+        let too_many_digits = match 10u128.checked_pow(precision.into()) {
+            Some(limit) => value.unsigned_abs() >= limit,
+            None => false,
+        };
+        if too_many_digits {
+            return Err(conversion_error::<Self>(format!(
+                "value exceeds the precision of DECIMAL({precision}, {scale})"
+            )));
+        }
+        Ok(Some(ScaledDecimal(value, scale)))
+    }
+}
+
+/// A value of a GEOMETRY column as sent by the server.
+///
+/// Depending on the version and configuration of the server, geometries are
+/// sent either as hex encoded Well-Known Binary, which is decoded like a BLOB,
+/// or as Well-Known Text such as `POINT (1 2)`. Either can be passed on to a
+/// GIS crate for further interpretation. Other column types are a conversion
+/// error.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RawGeometry {
+    Wkb(Vec<u8>),
+    Wkt(String),
+}
+
+impl FromMonet for RawGeometry {
+    fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+        let Some(field) = rs.row_set.get_field_raw(colnr) else {
+            return Ok(None);
+        };
+        let sql_type = rs.columns[colnr].sql_type();
+        if *sql_type != MonetType::Geometry {
+            return Err(conversion_error::<Self>(format!(
+                "expected GEOMETRY column, found {sql_type}"
+            )));
+        }
+        // WKT always starts with a keyword such as POINT, which is not hex
+        let geometry = match hex::decode(field) {
+            Ok(wkb) if !wkb.is_empty() => RawGeometry::Wkb(wkb),
+            _ => RawGeometry::Wkt(from_utf8(field)?.to_string()),
+        };
+        Ok(Some(geometry))
     }
 }
 
@@ -154,6 +448,23 @@ pub(crate) fn from_utf8(field: &[u8]) -> CursorResult<&str> {
 
 /// Apply the function to the raw result set field, converting any errors to [`CursorError`].
 pub(crate) fn transform<F, T, E>(field: &[u8], f: F) -> CursorResult<Option<T>>
+where
+    F: for<'x> FnOnce(&'x str) -> Result<T, E>,
+    E: error::Error + Send + Sync + 'static,
+    T: Any,
+{
+    let s = from_utf8(field)?;
+    match f(s) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => Err(conversion_error_from::<T>(e)),
+    }
+}
+
+/// Like [`transform`] but for error types that only implement
+/// [`Display`](`fmt::Display`). The error message is kept but there is no
+/// [`source()`](`std::error::Error::source`).
+#[cfg(feature = "decimal-rs")]
+pub(crate) fn transform_display<F, T, E>(field: &[u8], f: F) -> CursorResult<Option<T>>
 where
     F: for<'x> FnOnce(&'x str) -> Result<T, E>,
     E: fmt::Display,
@@ -170,14 +481,25 @@ where
 pub(crate) fn transform_fromstr<T>(field: &[u8]) -> CursorResult<Option<T>>
 where
     T: FromStr + Any,
-    <T as FromStr>::Err: fmt::Display,
+    <T as FromStr>::Err: error::Error + Send + Sync + 'static,
 {
     transform(field, |s| s.parse())
 }
 
-fn conversion_error<T: Any>(e: impl fmt::Display) -> CursorError {
+/// Create a [`CursorError::Conversion`] for a failure to convert to `T`.
+pub fn conversion_error<T: ?Sized>(e: impl fmt::Display) -> CursorError {
+    CursorError::Conversion {
+        expected_type: type_name::<T>(),
+        message: e.to_string().into(),
+        source: None,
+    }
+}
+
+/// Like [`conversion_error`] but keep `e` as the source of the error.
+pub fn conversion_error_from<T: Any>(e: impl error::Error + Send + Sync + 'static) -> CursorError {
     CursorError::Conversion {
         expected_type: type_name::<T>(),
         message: e.to_string().into(),
+        source: Some(ErrorSource::new(e)),
     }
 }
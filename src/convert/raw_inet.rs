@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+use std::{
+    fmt,
+    net::{AddrParseError, IpAddr},
+    num::ParseIntError,
+    str::FromStr,
+};
+
+/// Representation of an INET value from MonetDB, including the optional
+/// network prefix length, as in `192.168.1.0/24`.
+///
+/// Extracting an INET as a plain [`IpAddr`] only works if it has no prefix.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct RawInet {
+    pub addr: IpAddr,
+    pub prefix: Option<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidInet {
+    #[error(transparent)]
+    Addr(#[from] AddrParseError),
+    #[error("invalid prefix length: {0}")]
+    Prefix(#[from] ParseIntError),
+    #[error("prefix length {0} too large for address")]
+    PrefixTooLarge(u8),
+}
+
+impl FromStr for RawInet {
+    type Err = InvalidInet;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.parse()?)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.parse()?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        if let Some(p) = prefix {
+            if p > max {
+                return Err(InvalidInet::PrefixTooLarge(p));
+            }
+        }
+        Ok(RawInet { addr, prefix })
+    }
+}
+
+impl fmt::Display for RawInet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.addr)?;
+        if let Some(prefix) = self.prefix {
+            write!(f, "/{prefix}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_inet() {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let inet: RawInet = "192.168.1.0/24".parse().unwrap();
+    assert_eq!(inet.addr, IpAddr::V4(Ipv4Addr::new(192, 168, 1, 0)));
+    assert_eq!(inet.prefix, Some(24));
+    assert_eq!(inet.to_string(), "192.168.1.0/24");
+
+    let inet: RawInet = "10.0.0.1".parse().unwrap();
+    assert_eq!(inet.prefix, None);
+    assert_eq!(inet.to_string(), "10.0.0.1");
+
+    let inet: RawInet = "::1/128".parse().unwrap();
+    assert_eq!(inet.addr, IpAddr::V6(Ipv6Addr::LOCALHOST));
+    assert_eq!(inet.to_string(), "::1/128");
+
+    assert_eq!(
+        "10.0.0.0/33".parse::<RawInet>(),
+        Err(InvalidInet::PrefixTooLarge(33))
+    );
+    assert!("10.0.0.0/".parse::<RawInet>().is_err());
+    assert!("banana".parse::<RawInet>().is_err());
+}
@@ -12,7 +12,7 @@ use atoi::FromRadix10Checked;
 use bstr::BStr;
 use num::Zero;
 
-use crate::{cursor::replies::ResultSet, CursorResult};
+use crate::{cursor::replies::ResultSet, monettypes::Scale, CursorResult};
 
 use super::{conversion_error, raw_decimal::RawDecimal, FromMonet};
 
@@ -132,6 +132,34 @@ impl RawTime {
     pub fn microseconds(&self) -> u32 {
         self.microseconds + 1_000_000 * self.seconds as u32
     }
+
+    /// Truncate the fractional seconds to the given number of decimals,
+    /// typically the [declared precision][`crate::MonetType::fractional_digits`]
+    /// of the column. Precisions of 6 or more leave the value unchanged.
+    pub fn with_precision(self, decimals: Scale) -> RawTime {
+        let Some(unit) = 10u32.checked_pow(6u32.saturating_sub(decimals as u32)) else {
+            return self;
+        };
+        RawTime {
+            microseconds: self.microseconds - self.microseconds % unit,
+            ..self
+        }
+    }
+}
+
+#[test]
+fn test_time_with_precision() {
+    let t = RawTime {
+        microseconds: 123_456,
+        seconds: 1,
+        minutes: 2,
+        hours: 3,
+    };
+    assert_eq!(t.with_precision(0).microseconds, 0);
+    assert_eq!(t.with_precision(3).microseconds, 123_000);
+    assert_eq!(t.with_precision(6).microseconds, 123_456);
+    assert_eq!(t.with_precision(9).microseconds, 123_456);
+    assert_eq!(t.with_precision(3).seconds, 1);
 }
 
 #[test]
@@ -222,6 +250,15 @@ impl RawTimestamp {
         let time = RawTime::parse(field)?;
         Ok(RawTimestamp { date, time })
     }
+
+    /// Truncate the fractional seconds to the given number of decimals,
+    /// see [`RawTime::with_precision`].
+    pub fn with_precision(self, decimals: Scale) -> RawTimestamp {
+        RawTimestamp {
+            time: self.time.with_precision(decimals),
+            ..self
+        }
+    }
 }
 
 #[test]
@@ -281,6 +318,12 @@ impl RawTz {
         let hours = take_unsigned::<u8, Self>(hr_ms)?;
         take_sep::<Self>(hr_ms, b":")?;
         let mins: u8 = take_unsigned::<u8, Self>(hr_ms)?;
+        // SQL allows offsets from -14:00 to +14:00
+        if mins >= 60 || (hours, mins) > (14, 0) {
+            return Err(conversion_error::<Self>(format!(
+                "timezone offset out of range: {hours:02}:{mins:02}"
+            )));
+        }
         let seconds_east = sign * (3600 * hours as i32 + 60 * mins as i32);
         *field = *hr_ms;
         Ok(RawTz { seconds_east })
@@ -317,6 +360,25 @@ fn test_parse_tz() {
     claims::assert_err!(RawTz::parse(&mut s));
     s = b"+00";
     claims::assert_err!(RawTz::parse(&mut s));
+
+    s = b"+14:00";
+    assert_eq!(
+        RawTz::parse(&mut s),
+        Ok(RawTz {
+            seconds_east: 14 * 3600
+        })
+    );
+    s = b"-14:00";
+    assert_eq!(
+        RawTz::parse(&mut s),
+        Ok(RawTz {
+            seconds_east: -14 * 3600
+        })
+    );
+    for bad in ["+15:00", "+12:99", "-14:01", "+99:99", "+24:00"] {
+        s = bad.as_bytes();
+        claims::assert_err!(RawTz::parse(&mut s), "{bad}");
+    }
 }
 
 /// Representation of a TIME WITH TIMEZONE (TIMETZ) value from MonetDB
@@ -33,6 +33,7 @@ fn extract_from_fake_resultset<T: FromMonet + fmt::Debug>(
         result_id: 0,
         next_row: 0,
         total_rows: 1,
+        received_rows: 1,
         columns,
         row_set,
         stashed: None,
@@ -116,6 +117,22 @@ fn test_ints() {
     assert_parse_fails::<usize>("-87654");
 }
 
+#[test]
+fn test_conversion_error_source() {
+    use std::{error::Error, num::ParseIntError};
+
+    let err = extract_from_fake_resultset::<i8>(MonetType::Int, "87654").unwrap_err();
+    let source = err.source().expect("conversion error should have a source");
+    assert!(source.downcast_ref::<ParseIntError>().is_some());
+
+    let err = extract_from_fake_resultset::<RawDecimal<i8>>(MonetType::Int, "1.28").unwrap_err();
+    let source = err.source().unwrap();
+    assert_eq!(
+        source.downcast_ref::<raw_decimal::InvalidDecimal>(),
+        Some(&raw_decimal::InvalidDecimal::OutOfRange)
+    );
+}
+
 #[test]
 fn test_rawdecimal() {
     assert_parses("1.23", RawDecimal(123i32, 2));
@@ -150,11 +167,149 @@ fn test_bool() {
     assert_parse_fails::<bool>("True");
 }
 
+#[test]
+fn test_int_bool() {
+    let extract = |coltype, field| extract_from_fake_resultset::<IntBool>(coltype, field);
+    assert_eq!(extract(MonetType::TinyInt, "0"), Ok(Some(IntBool(false))));
+    assert_eq!(extract(MonetType::TinyInt, "1"), Ok(Some(IntBool(true))));
+    assert_eq!(extract(MonetType::BigInt, "-7"), Ok(Some(IntBool(true))));
+    assert_eq!(extract(MonetType::Bool, "true"), Ok(Some(IntBool(true))));
+    assert_eq!(extract(MonetType::Bool, "false"), Ok(Some(IntBool(false))));
+
+    assert_err!(extract(MonetType::Bool, "1"));
+    assert_err!(extract(MonetType::TinyInt, "true"));
+    assert_err!(extract(MonetType::Varchar(10), "1"));
+}
+
+#[test]
+fn test_scaled_decimal() {
+    let extract = |coltype, field| extract_from_fake_resultset::<ScaledDecimal>(coltype, field);
+    let dec = MonetType::Decimal(38, 10);
+    assert_eq!(
+        extract(dec, "-12.3456789012"),
+        Ok(Some(ScaledDecimal(-123456789012, 10)))
+    );
+    assert_eq!(
+        extract(dec, "1234567890123456789012345678.0000000001"),
+        Ok(Some(ScaledDecimal(
+            12345678901234567890123456780000000001,
+            10
+        )))
+    );
+    assert_eq!(
+        extract(MonetType::Decimal(3, 0), "-999"),
+        Ok(Some(ScaledDecimal(-999, 0)))
+    );
+    assert_eq!(extract(dec, "NULL"), Ok(None));
+
+    // scale must match the column
+    assert_err!(extract(dec, "1.5"));
+    // too many digits for the precision
+    assert_err!(extract(MonetType::Decimal(3, 1), "100.0"));
+    // not a DECIMAL column
+    assert_err!(extract(MonetType::Int, "42"));
+    assert_err!(extract(MonetType::Double, "1.5"));
+}
+
+#[test]
+fn test_field_bytes() {
+    // Only uses the public API, like an implementation outside the crate
+    #[derive(Debug, PartialEq)]
+    struct Tagged(MonetType, i64);
+
+    impl FromMonet for Tagged {
+        fn extract(rs: &ResultSet, colnr: usize) -> CursorResult<Option<Self>> {
+            assert_eq!(rs.columns().len(), 2);
+            let Some(field) = rs.field(colnr) else {
+                return Ok(None);
+            };
+            assert_eq!(field.bytes(), field.as_str()?.as_bytes());
+            Ok(Some(Tagged(*field.sql_type(), field.parse()?)))
+        }
+    }
+
+    let extract = |coltype, field| extract_from_fake_resultset::<Tagged>(coltype, field);
+    assert_eq!(
+        extract(MonetType::Int, "42"),
+        Ok(Some(Tagged(MonetType::Int, 42)))
+    );
+    let err = extract(MonetType::Int, "banana").unwrap_err();
+    assert_matches!(
+        err,
+        CursorError::Conversion {
+            expected_type: "i64",
+            ..
+        }
+    );
+}
+
+#[test]
+fn test_string() {
+    assert_parses("foo", "foo".to_string());
+    assert_parses("42", "42".to_string());
+    assert_parses("", "".to_string());
+}
+
+#[test]
+fn test_char() {
+    assert_parses("x", 'x');
+    assert_parses("\u{e9}", '\u{e9}');
+    assert_parses("\u{1f980}", '\u{1f980}');
+    assert_parse_fails::<char>("");
+    assert_parse_fails::<char>("xy");
+}
+
+#[test]
+fn test_option() {
+    // Not using extract_from_fake_resultset because it expects NULL to
+    // yield Ok(None).
+    let columns = vec![
+        ResultColumn::new("%0", MonetType::Int),
+        ResultColumn::new("%1", MonetType::Int),
+        ResultColumn::new("%2", MonetType::Int),
+    ];
+    let body = b"[ NULL,\t42,\tforty-two\t]\n".to_vec();
+    let mut row_set = RowSet::new(ReplyBuf::new(body), columns.len());
+    row_set.advance().unwrap();
+    let rs = ResultSet {
+        result_id: 0,
+        next_row: 0,
+        total_rows: 1,
+        received_rows: 1,
+        columns,
+        row_set,
+        stashed: None,
+        to_close: None,
+    };
+    assert_eq!(<Option<i32>>::extract(&rs, 0), Ok(Some(None)));
+    assert_eq!(<Option<i32>>::extract(&rs, 1), Ok(Some(Some(42))));
+    assert_err!(<Option<i32>>::extract(&rs, 2));
+}
+
 #[test]
 fn test_blob() {
     assert_parses("466f6f", Vec::from(b"Foo"));
 }
 
+#[test]
+fn test_geometry() {
+    let extract = |coltype, field| extract_from_fake_resultset::<RawGeometry>(coltype, field);
+    let point_wkb = "0101000000000000000000F03F0000000000000040";
+    let Ok(Some(RawGeometry::Wkb(wkb))) = extract(MonetType::Geometry, point_wkb) else {
+        panic!("expected WKB");
+    };
+    assert_eq!(wkb.len(), 21);
+    assert_eq!(wkb[..5], [1, 1, 0, 0, 0]);
+
+    assert_eq!(
+        extract(MonetType::Geometry, "\"POINT (1 2)\""),
+        Ok(Some(RawGeometry::Wkt("POINT (1 2)".to_string())))
+    );
+    assert_eq!(extract(MonetType::Geometry, "NULL"), Ok(None));
+    assert_err!(extract(MonetType::Blob, point_wkb));
+    assert_eq!(MonetType::prototype("geometrya"), Some(MonetType::Geometry));
+}
+
 #[test]
 #[cfg(feature = "uuid")]
 fn test_uuid() {
@@ -275,3 +430,20 @@ fn test_rawtimetz() {
     assert_parse_fails::<RawTimeTz>("12:34:56.789");
     assert_parse_fails::<RawTimeTz>("12:34:56.789+02:00xyz");
 }
+
+#[test]
+fn test_inet() {
+    let addr = IpAddr::from([192, 168, 1, 1]);
+    assert_parses("192.168.1.1", addr);
+    assert_parse_fails::<IpAddr>("192.168.1.0/24");
+
+    assert_parses(
+        "192.168.1.0/24",
+        RawInet {
+            addr: IpAddr::from([192, 168, 1, 0]),
+            prefix: Some(24),
+        },
+    );
+    assert_parses("192.168.1.1", RawInet { addr, prefix: None });
+    assert_parse_fails::<RawInet>("192.168.1.0/40");
+}
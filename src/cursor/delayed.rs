@@ -9,11 +9,12 @@
 #![allow(dead_code)]
 
 use core::fmt;
-use std::{borrow::Cow, io::Write};
+use std::borrow::Cow;
 
 use crate::framing::{reading::MapiReader, writing::MapiBuf, ServerSock};
 
-use super::CursorResult;
+use super::replies::ReplyParser;
+use super::{CursorError, CursorResult};
 
 pub struct ExpectedResponse {
     pub description: Cow<'static, str>,
@@ -54,8 +55,7 @@ impl DelayedCommands {
     }
 
     pub fn send_delayed(&mut self, mut conn: ServerSock) -> CursorResult<ServerSock> {
-        let raw = self.buffer.reset();
-        conn.write_all(raw)?;
+        conn = self.buffer.flush_reset(conn)?;
         Ok(conn)
     }
 
@@ -68,32 +68,60 @@ impl DelayedCommands {
         Ok(conn)
     }
 
+    /// Receive the responses to the delayed commands that have been sent.
+    ///
+    /// All responses are read, even if some of them are errors, so the
+    /// connection stays in sync and the reply to whatever was sent after the
+    /// delayed commands can still be received. The outer error is an IO
+    /// error, after which the connection is unusable. The inner result holds
+    /// the first error response, if any.
     pub fn recv_delayed(
         &mut self,
         conn: ServerSock,
         buffer: &mut Vec<u8>,
-    ) -> CursorResult<ServerSock> {
-        let res = self.recv_delayed_inner(conn, buffer);
-        buffer.clear();
-        res
+    ) -> CursorResult<(ServerSock, CursorResult<()>)> {
+        self.recv_delayed_with(conn, buffer, |_, _| Ok(()))
     }
 
-    pub fn recv_delayed_inner(
+    /// Like [`recv_delayed`](`DelayedCommands::recv_delayed`) but also passes
+    /// each non-error response to `check`, together with the description of the
+    /// command it belongs to. Once a response has been found to be an error,
+    /// the remaining responses are read but not checked.
+    pub fn recv_delayed_with<F>(
         &mut self,
         mut conn: ServerSock,
         buffer: &mut Vec<u8>,
-    ) -> CursorResult<ServerSock> {
+        mut check: F,
+    ) -> CursorResult<(ServerSock, CursorResult<()>)>
+    where
+        F: FnMut(&str, &[u8]) -> CursorResult<()>,
+    {
+        let mut result = Ok(());
         for resp in self.responses.drain(..) {
             buffer.clear();
             conn = MapiReader::to_end(conn, buffer)?;
-            if let Some(err_msg) = buffer.strip_prefix(b"!") {
-                let msg = String::from_utf8_lossy(err_msg);
-                let description = &resp.description;
-                return Err(super::CursorError::Server(format!(
-                    "delayed {description}: {msg}"
-                )));
+            if result.is_err() {
+                continue;
             }
+            let description = &resp.description;
+            // A statement can produce several replies, the error is not
+            // necessarily the first one.
+            result = match ReplyParser::detect_errors(buffer) {
+                Err(CursorError::Server(message)) => Err(CursorError::DelayedCommand {
+                    command: description.to_string(),
+                    message,
+                }),
+                Err(err) => Err(err),
+                Ok(()) => check(description, buffer),
+            };
         }
-        Ok(conn)
+        buffer.clear();
+        Ok((conn, result))
+    }
+
+    /// Return `true` if a command with the given description is waiting
+    /// for its response.
+    pub fn expects(&self, description: &str) -> bool {
+        self.responses.iter().any(|r| r.description == description)
     }
 }
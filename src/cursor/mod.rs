@@ -9,24 +9,61 @@
 #![allow(dead_code)]
 
 pub(crate) mod delayed;
+mod output;
+#[cfg(feature = "arrow")]
+mod record_batch;
 pub(crate) mod replies;
 pub(crate) mod rowset;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::hash::Hash;
 use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use std::{io, sync::Arc};
 
 use delayed::DelayedCommands;
-use replies::{BadReply, ReplyBuf, ReplyParser, ResultColumn, ResultSet};
+use replies::{
+    prepared_column_type, quoted_columns, BadReply, ReplyBuf, ReplyKind, ReplyParser, ResultColumn,
+    ResultSet,
+};
 use rowset::RowSet;
 
 use crate::conn::Conn;
-use crate::convert::{from_utf8, FromMonet};
+use crate::convert::{
+    conversion_error, from_utf8, non_null, FromMonet, FromRow, IntBool, ScaledDecimal,
+};
 use crate::framing::reading::MapiReader;
 use crate::framing::writing::MapiBuf;
 use crate::framing::FramingError;
 use crate::framing::{ServerSock, ServerState};
+use crate::observer::{QueryEvent, QueryEventKind, SharedQueryObserver};
+use crate::sqlgen::{redact_for_log, ToMonetSql};
+use crate::util::errorsource::ErrorSource;
 use crate::util::ioerror::IoError;
+use crate::MonetType;
+
+/// Execute a query built with [`format!`]-style arguments, without
+/// allocating an intermediate `String`. Expands to a call to
+/// [`Cursor::execute_fmt`].
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut cursor: monetdb::Cursor = todo!();
+/// let table = "mytable";
+/// let limit = 10;
+/// monetdb::execute!(cursor, "SELECT * FROM {table} LIMIT {limit}")?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! execute {
+    ($cursor:expr, $($arg:tt)*) => {
+        $cursor.execute_fmt(::std::format_args!($($arg)*))
+    };
+}
 
 /// An error that occurs while accessing data with a [`Cursor`].
 #[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
@@ -52,13 +89,73 @@ pub enum CursorError {
     NoResultSet,
     /// The user called the wrong typed getter, for example
     /// [`get_bool()`](`Cursor::get_bool`) on an INT column.
+    /// If the conversion failed because of an underlying parse error, that
+    /// error is available through [`source()`](`std::error::Error::source`).
     #[error("could not convert to {expected_type}: {message}")]
     Conversion {
         expected_type: &'static str,
         message: Cow<'static, str>,
+        source: Option<ErrorSource>,
     },
+    /// The result set has no column with the given name, see
+    /// [`column_index()`](`Cursor::column_index`).
+    #[error("no column named '{0}'")]
+    UnknownColumn(String),
+    /// A column index passed to, for example,
+    /// [`collect_column()`](`Cursor::collect_column`) is not less than the
+    /// number of columns of the result set.
+    #[error("column index {index} out of range, result set has {columns} columns")]
+    ColumnIndex { index: usize, columns: usize },
     #[error("could not retrieve server metadata: {0}")]
     Metadata(&'static str),
+    /// The connection failed while more rows of a result set were being
+    /// retrieved. Subsequent operations on the connection return
+    /// [`Closed`](`CursorError::Closed`).
+    #[error("connection lost while retrieving result set: {0}")]
+    ConnectionLostMidResult(#[source] IoError),
+    /// The cancel token set with
+    /// [`with_cancel_token()`](`Cursor::with_cancel_token`) was raised.
+    #[error("cancelled")]
+    Cancelled,
+    /// Another thread is using the connection, see
+    /// [`Connection::try_cursor_op()`](`crate::Connection::try_cursor_op`).
+    #[error("connection is busy")]
+    Busy,
+    /// [`affected_then_rows()`](`Cursor::affected_then_rows`) was called but
+    /// the current reply does not report a number of affected rows.
+    #[error("the current reply is not an update")]
+    NotAnUpdate,
+    /// A query that should return at most one row, see
+    /// [`Connection::query_opt()`](`crate::Connection::query_opt`), returned
+    /// more.
+    #[error("query returned more than one row")]
+    TooManyRows,
+    /// The number of columns of the result set does not match the number of
+    /// elements of the tuple passed to [`get_row()`](`Cursor::get_row`).
+    #[error("expected {expected} columns, result set has {actual}")]
+    ColumnCount { expected: usize, actual: usize },
+    /// A row passed to [`execute_many()`](`Cursor::execute_many`) has a
+    /// different number of values than the first row.
+    #[error("row {row} has {actual} values, expected {expected}")]
+    RowLength {
+        row: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// A reply of `bytes` bytes exceeded the
+    /// [`max_result_bytes`](`crate::Parameters::set_max_result_bytes`)
+    /// setting. The rest of the reply has been discarded, the connection is
+    /// still usable. If a [delayed command](`CursorError::DelayedCommand`)
+    /// failed as well, its error is available through
+    /// [`last_error_detail()`](`Cursor::last_error_detail`).
+    #[error("reply of {bytes} bytes exceeds max_result_bytes {limit}")]
+    ResultTooLarge { bytes: usize, limit: usize },
+    /// A command that was queued to be sent along with the next request, for
+    /// example by [`Connection::set_default_reply_size()`](`crate::Connection::set_default_reply_size`),
+    /// was rejected by the server. Statements sent in the same request have
+    /// still been executed and their reply is available on the cursor.
+    #[error("delayed {command}: {message}")]
+    DelayedCommand { command: String, message: String },
 }
 
 pub type CursorResult<T> = Result<T, CursorError>;
@@ -94,7 +191,27 @@ impl From<io::Error> for CursorError {
 /// and they expect to be able to directly retrieve the count, not get an error
 /// message "CREATE TABLE did not return a result set". Note that
 /// [`next_row()`][`Cursor::next_row`] will *not* automatically skip to the next
-/// result set if the current result set is exhausted.
+/// result set if the current result set is exhausted. Use
+/// [`set_strict_result_sets()`][`Cursor::set_strict_result_sets`] to turn off
+/// the automatic skipping.
+///
+/// [`execute()`][`Cursor::execute`] only returns once the server has replied
+/// to all statements. The replies themselves are small: result sets
+/// only include their first rows, see the `replysize` setting, and the rest is
+/// fetched on demand by [`next_row()`][`Cursor::next_row`]. A pipelined mode
+/// that returns as soon as the first reply has arrived is not offered, for
+/// these reasons:
+///
+/// * The server generally only flushes its output once it has processed all
+///   statements in the message, so for a script whose first statement is a
+///   quick SELECT the first reply would not arrive much earlier.
+/// * Until the rest of the replies has been read, the connection cannot be
+///   used for anything else, not even by other cursors or to close result
+///   sets. An abandoned half-read reply would have to be drained first.
+/// * Errors in later statements would only surface while iterating over the
+///   first result set, rather than from `execute()`.
+///
+/// If only the first result matters, execute that statement on its own.
 ///
 /// To retrieve data from a result set, first call
 /// [`next_row()`][`Cursor::next_row`]. This tries to move the cursor to the
@@ -117,11 +234,31 @@ impl From<io::Error> for CursorError {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// A Cursor is `Send` but not `Sync`: it can be moved to another thread but
+/// not used from several threads at once. Create a cursor per thread instead,
+/// see [`Connection`](`crate::Connection`) for how they share the connection.
 pub struct Cursor {
     conn: Arc<Conn>,
     buf: MapiBuf,
     replies: ReplyParser,
     reply_size: usize,
+    cancel_token: Option<Arc<AtomicBool>>,
+    row_cap: Option<u64>,
+    strict_result_sets: bool,
+    lossy_utf8: bool,
+    retained_blocks: Option<Vec<Vec<u8>>>,
+    try_lock: bool,
+    last_error: Option<String>,
+    observed: Option<ObservedQuery>,
+}
+
+/// The statements most recently executed while a query observer was installed.
+struct ObservedQuery {
+    observer: SharedQueryObserver,
+    sql: String,
+    start: Instant,
+    done_reported: bool,
 }
 
 impl Cursor {
@@ -129,49 +266,429 @@ impl Cursor {
         Cursor {
             buf: MapiBuf::new(),
             replies: ReplyParser::default(),
-            reply_size: conn.reply_size,
+            reply_size: conn.reply_size.load(Ordering::SeqCst),
+            cancel_token: None,
+            row_cap: None,
+            strict_result_sets: false,
+            lossy_utf8: false,
+            retained_blocks: None,
+            try_lock: false,
+            last_error: None,
+            observed: None,
             conn,
         }
     }
 
+    /// Install a cancel token. When another thread sets it to `true`,
+    /// [`next_row()`](`Cursor::next_row`) returns [`CursorError::Cancelled`]
+    /// instead of moving to the next row.
+    ///
+    /// The token is only checked between rows and before fetching more rows
+    /// from the server. A blocking read that is already in progress is not
+    /// interrupted, the cancellation takes effect once it returns.
+    /// The cursor never clears the token, that is up to the caller.
+    pub fn set_cancel_token(&mut self, token: Arc<AtomicBool>) {
+        self.cancel_token = Some(token);
+    }
+
+    /// Builder-style variant of [`set_cancel_token()`](`Cursor::set_cancel_token`).
+    pub fn with_cancel_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.set_cancel_token(token);
+        self
+    }
+
+    /// When enabled, [`next_row()`](`Cursor::next_row`) and the other methods
+    /// that need a result set return [`CursorError::NoResultSet`] if the
+    /// current reply is not a result set, instead of skipping ahead to the
+    /// next result set. Disabled by default.
+    pub fn set_strict_result_sets(&mut self, strict: bool) {
+        self.strict_result_sets = strict;
+    }
+
+    /// Builder-style variant of
+    /// [`set_strict_result_sets()`](`Cursor::set_strict_result_sets`).
+    pub fn with_strict_result_sets(mut self, strict: bool) -> Self {
+        self.set_strict_result_sets(strict);
+        self
+    }
+
+    /// When enabled, [`get_cow_str()`](`Cursor::get_cow_str`) replaces invalid
+    /// UTF-8 in the values it retrieves with U+FFFD REPLACEMENT CHARACTER
+    /// instead of returning an error. The other getters are not affected.
+    /// Disabled by default.
+    pub fn set_lossy_utf8(&mut self, lossy: bool) {
+        self.lossy_utf8 = lossy;
+    }
+
+    /// Builder-style variant of [`set_lossy_utf8()`](`Cursor::set_lossy_utf8`).
+    pub fn with_lossy_utf8(mut self, lossy: bool) -> Self {
+        self.set_lossy_utf8(lossy);
+        self
+    }
+
+    /// When enabled, the cursor keeps a copy of every reply the server sends
+    /// to execute statements and to fetch more rows, so the raw bytes can be
+    /// inspected with [`retained_blocks()`](`Cursor::retained_blocks`) after
+    /// the result sets have been processed. This is meant for diagnosing
+    /// problems with parsing replies and can use a lot of memory, so it is
+    /// disabled by default. Disabling it discards the retained replies.
+    pub fn set_retain_blocks(&mut self, retain: bool) {
+        match (retain, &self.retained_blocks) {
+            (true, None) => self.retained_blocks = Some(vec![]),
+            (true, Some(_)) => {}
+            (false, _) => self.retained_blocks = None,
+        }
+    }
+
+    /// Builder-style variant of [`set_retain_blocks()`](`Cursor::set_retain_blocks`).
+    pub fn with_retain_blocks(mut self, retain: bool) -> Self {
+        self.set_retain_blocks(retain);
+        self
+    }
+
+    /// Return the replies retained since statements were last sent to the
+    /// server, in the order they were received, see
+    /// [`set_retain_blocks()`](`Cursor::set_retain_blocks`). Empty if
+    /// retaining is disabled.
+    ///
+    /// The first block is the reply to the statements themselves, the others
+    /// are the replies to the requests for more rows.
+    pub fn retained_blocks(&self) -> &[Vec<u8>] {
+        self.retained_blocks.as_deref().unwrap_or_default()
+    }
+
+    fn retain_block(&mut self, block: &[u8]) {
+        if let Some(retained) = &mut self.retained_blocks {
+            retained.push(block.to_vec());
+        }
+    }
+
+    /// Return [`CursorError::Busy`] rather than wait when another thread is
+    /// using the connection.
+    pub(crate) fn set_try_lock(&mut self, try_lock: bool) {
+        self.try_lock = try_lock;
+    }
+
+    fn check_cancelled(&self) -> CursorResult<()> {
+        match &self.cancel_token {
+            Some(token) if token.load(Ordering::Relaxed) => Err(CursorError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
     /// Execute the given SQL statements and place the cursor at the first
     /// reply. The results of any earlier queries on this cursor are discarded.
+    ///
+    /// If the statements succeeded but a setting change that was sent along
+    /// with them was rejected, [`CursorError::DelayedCommand`] is returned and
+    /// the cursor is still placed at the first reply.
     pub fn execute(&mut self, statements: &str) -> CursorResult<()> {
+        self.execute_inner(format_args!("{statements}"), None)
+    }
+
+    /// Like [`execute()`][`Cursor::execute`] but the statements are formatted
+    /// directly into the outgoing message, without first allocating a
+    /// `String`. Usually invoked through the [`execute!`](`crate::execute!`)
+    /// macro.
+    ///
+    /// Note that this does not do any quoting, the formatted values end up in
+    /// the SQL as-is.
+    pub fn execute_fmt(&mut self, statements: fmt::Arguments) -> CursorResult<()> {
+        self.execute_inner(statements, None)
+    }
+
+    /// Like [`execute()`][`Cursor::execute`] but the statements are given as a
+    /// sequence of fragments that are sent one after the other, without first
+    /// concatenating them into a single `String`. This is useful when
+    /// generating large statements from many pieces, for example a wide
+    /// `INSERT`.
+    ///
+    /// Note that no separators are added between the fragments.
+    pub fn execute_parts<I>(&mut self, parts: I) -> CursorResult<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
         self.exhaust()?;
+        self.row_cap = None;
+        self.last_error = None;
+
+        // Only keep a copy of the text if something is going to log it
+        let keep_text = self.conn.slow_query_log.is_some() || self.conn.query_observer().is_some();
+        let mut text = String::new();
+        self.buf.append("s");
+        for part in parts {
+            let part = part.as_ref();
+            self.buf.append(part);
+            if keep_text {
+                text.push_str(&String::from_utf8_lossy(part));
+            }
+        }
+        self.buf.append("\n;");
+
+        self.send_statements(&|| text.clone())
+    }
+
+    /// Like [`execute()`][`Cursor::execute`] but never retrieve more than
+    /// `max_rows` rows of each result set.
+    /// [`next_row()`][`Cursor::next_row`] returns `false` when the cap has been
+    /// reached, even if the server has more rows. These remaining rows are
+    /// released when the cursor moves on to the next reply or is closed.
+    ///
+    /// This is useful to protect interactive applications against
+    /// accidentally retrieving huge result sets.
+    pub fn execute_capped(&mut self, statements: &str, max_rows: u64) -> CursorResult<()> {
+        self.execute_inner(format_args!("{statements}"), Some(max_rows))
+    }
+
+    /// Insert many rows using as few multi-row `INSERT` statements as
+    /// possible, returning the total number of affected rows.
+    ///
+    /// The `template` is the part of the statement before the rows, for
+    /// example `INSERT INTO foo(i, t) VALUES` or just `INSERT INTO foo`, in
+    /// which case `VALUES` is appended. The values are rendered as SQL
+    /// literals using [`ToMonetSql`], so for example `None` becomes `NULL`.
+    /// Rows are split over several statements to keep the statements
+    /// reasonably small.
+    ///
+    /// All rows must have the same number of values, otherwise
+    /// [`CursorError::RowLength`] is returned before anything is sent. A value
+    /// that cannot be rendered, such as a NaN float, is reported as
+    /// [`CursorError::Conversion`].
+    ///
+    /// If one of the statements fails, the rows of the statements before it
+    /// have already been inserted and are not included in the error. Use a
+    /// transaction to insert all rows or none.
+    pub fn execute_many<V: ToMonetSql>(
+        &mut self,
+        template: &str,
+        rows: &[&[V]],
+    ) -> CursorResult<i64> {
+        const MAX_STATEMENT_SIZE: usize = 1024 * 1024;
+
+        let template = template.trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+        let keyword = template
+            .len()
+            .checked_sub(6)
+            .and_then(|i| template.get(i..));
+        let prefix = if keyword.is_some_and(|k| k.eq_ignore_ascii_case("VALUES")) {
+            template.to_string()
+        } else {
+            format!("{template} VALUES")
+        };
+
+        if let Some(first) = rows.first() {
+            let expected = first.len();
+            if let Some((row, r)) = rows.iter().enumerate().find(|(_, r)| r.len() != expected) {
+                let actual = r.len();
+                return Err(CursorError::RowLength {
+                    row,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        let mut total = 0;
+        let mut sql = String::new();
+        for (i, row) in rows.iter().enumerate() {
+            sql.push_str(if sql.is_empty() { &prefix } else { "," });
+            sql.push_str("\n(");
+            for (j, value) in row.iter().enumerate() {
+                if j > 0 {
+                    sql.push_str(", ");
+                }
+                if value.write_sql(&mut sql).is_err() {
+                    let msg = format_args!("value {j} of row {i} has no SQL representation");
+                    return Err(conversion_error::<V>(msg));
+                }
+            }
+            sql.push(')');
+            if sql.len() >= MAX_STATEMENT_SIZE || i + 1 == rows.len() {
+                self.execute(&sql)?;
+                total += self.affected_rows().unwrap_or(0);
+                sql.clear();
+            }
+        }
+        Ok(total)
+    }
+
+    fn execute_inner(
+        &mut self,
+        statements: fmt::Arguments,
+        row_cap: Option<u64>,
+    ) -> CursorResult<()> {
+        self.exhaust()?;
+        self.row_cap = row_cap;
+        self.last_error = None;
+
+        // Format into our own buffer rather than the shared one so a failing
+        // Display implementation cannot leave a half-written message behind.
+        if write!(self.buf, "s{statements}\n;").is_err() {
+            self.buf.reset();
+            let err = io::Error::other("formatter error while rendering statements");
+            return Err(err.into());
+        }
+
+        self.send_statements(&|| statements.to_string())
+    }
+
+    /// Send the statements that have been written to `self.buf` and process
+    /// the replies. `sql` renders the statements for logging.
+    fn send_statements(&mut self, sql: &dyn Fn() -> String) -> CursorResult<()> {
+        self.observed = self.conn.query_observer().map(|observer| ObservedQuery {
+            observer,
+            sql: redact_for_log(&sql()),
+            start: Instant::now(),
+            done_reported: false,
+        });
 
         let mut vec = self.replies.take_buffer();
-        let command = &[b"s", statements.as_bytes(), b"\n;"];
+        let start = Instant::now();
+        let (delayed_result, oversized) = match self.send_buf(&mut vec) {
+            Ok(received) => received,
+            Err(err) => {
+                self.notify_observer(QueryEventKind::Executed, None, None, Some(&err));
+                return Err(err);
+            }
+        };
+        self.conn.statements.fetch_add(1, Ordering::Relaxed);
+        if let Some(retained) = &mut self.retained_blocks {
+            retained.clear();
+        }
+        self.retain_block(&vec);
+        if let Some(threshold) = self.conn.slow_query_log {
+            let elapsed = start.elapsed();
+            if elapsed >= threshold {
+                let sql = redact_for_log(&sql());
+                warn!("slow query took {} ms: {sql}", elapsed.as_millis());
+            }
+        }
 
-        self.command(command, &mut vec)?;
+        if let (Some(bytes), Some(limit)) = (oversized, self.conn.max_result_bytes) {
+            // Only the beginning of the reply has been kept, release the
+            // result sets it mentions and discard the rest.
+            for res_id in ReplyParser::open_result_ids(&vec) {
+                self.queue_close(res_id)?;
+            }
+            vec.clear();
+            self.replies = ReplyParser::Exhausted(vec);
+            // Only one error can be returned, make sure a failed delayed
+            // command does not go unnoticed.
+            if let Err(err) = &delayed_result {
+                warn!("{err}");
+                self.last_error = Some(err.to_string());
+            }
+            let err = CursorError::ResultTooLarge { bytes, limit };
+            self.notify_observer(QueryEventKind::Executed, None, None, Some(&err));
+            return Err(err);
+        }
 
         let error = ReplyParser::detect_errors(&vec);
+        if error.is_err() {
+            self.last_error = ReplyParser::error_detail(&vec);
+        } else if let Err(err) = &delayed_result {
+            self.last_error = Some(err.to_string());
+        }
 
         // Always create and install a replyparser, even if an error occurred.
         // We need to make sure all result sets are being released etc.
         self.replies = ReplyParser::new(vec)?;
 
         if let Err(err) = error {
+            self.notify_observer(QueryEventKind::Executed, None, None, Some(&err));
             self.exhaust()?;
             return Err(err);
         }
 
-        Ok(())
+        // The statements have been executed but an earlier command that was
+        // sent along with them failed. Report that, but keep the reply so
+        // the results can still be read.
+        let affected_rows = self.affected_rows();
+        let result_rows = self.result_set().ok().map(|rs| rs.total_rows);
+        let error = delayed_result.as_ref().err();
+        self.notify_observer(QueryEventKind::Executed, affected_rows, result_rows, error);
+
+        delayed_result
+    }
+
+    fn notify_observer(
+        &self,
+        kind: QueryEventKind,
+        affected_rows: Option<i64>,
+        result_rows: Option<u64>,
+        error: Option<&CursorError>,
+    ) {
+        let Some(observed) = &self.observed else {
+            return;
+        };
+        let event = QueryEvent {
+            kind,
+            sql: &observed.sql,
+            duration: observed.start.elapsed(),
+            affected_rows,
+            result_rows,
+            error,
+        };
+        (observed.observer)(&event);
     }
 
-    fn command(&mut self, command: &[&[u8]], vec: &mut Vec<u8>) -> Result<(), CursorError> {
-        self.conn.run_locked(
+    /// Send the command along with any delayed commands and receive the reply
+    /// into `vec`, keeping at most `limit` bytes of it. If one of the delayed
+    /// commands failed, the reply is still received but the error is
+    /// returned. If the reply was longer than `limit`, its full size is
+    /// returned.
+    fn command(
+        &mut self,
+        command: &[&[u8]],
+        vec: &mut Vec<u8>,
+        limit: Option<usize>,
+    ) -> CursorResult<Option<usize>> {
+        let mut delayed_result = Ok(());
+        let mut oversized = None;
+        self.conn.run_locked_with(
+            !self.try_lock,
             |_state: &mut ServerState,
              delayed: &mut DelayedCommands,
              mut sock: ServerSock|
              -> CursorResult<ServerSock> {
                 sock = delayed.send_delayed_plus(sock, command)?;
-                sock = delayed.recv_delayed(sock, vec)?;
-                vec.clear();
-                sock = MapiReader::to_end(sock, vec)?;
+                (sock, delayed_result) = delayed.recv_delayed(sock, vec)?;
+                (sock, oversized) = receive_reply(sock, vec, limit)?;
                 Ok(sock)
             },
         )?;
-        Ok(())
+        delayed_result.map(|()| oversized)
+    }
+
+    /// Like [`command()`][`Cursor::command`] but send the message that has been
+    /// prepared in `self.buf` and use the
+    /// [`max_result_bytes`](`crate::Parameters::set_max_result_bytes`) limit.
+    /// The outer error means nothing has been received, the inner one that a
+    /// delayed command failed.
+    fn send_buf(&mut self, vec: &mut Vec<u8>) -> CursorResult<(CursorResult<()>, Option<usize>)> {
+        let Cursor {
+            conn,
+            buf,
+            try_lock,
+            ..
+        } = self;
+        let limit = conn.max_result_bytes;
+        let mut delayed_result = Ok(());
+        let mut oversized = None;
+        let ret = conn.run_locked_with(!*try_lock, |_state, delayed, mut sock| {
+            sock = delayed.send_delayed(sock)?;
+            sock = buf.write_reset(sock)?;
+            (sock, delayed_result) = delayed.recv_delayed(sock, vec)?;
+            (sock, oversized) = receive_reply(sock, vec, limit)?;
+            Ok(sock)
+        });
+        if ret.is_err() {
+            // either nothing has been sent or the connection is unusable,
+            // discard whatever is left of the message
+            buf.reset();
+        }
+        ret.map(|()| (delayed_result, oversized))
     }
 
     /// Retrieve the number of affected rows from the current reply. INSERT,
@@ -185,11 +702,94 @@ impl Cursor {
         self.replies.affected_rows()
     }
 
+    /// Return the number of rows affected by the current reply and move the
+    /// cursor to the next result set.
+    ///
+    /// MonetDB does not support `INSERT .. RETURNING`, but the same effect
+    /// can be achieved by executing an INSERT and a SELECT together:
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut cursor: monetdb::Cursor = todo!();
+    /// cursor.execute(
+    ///     "INSERT INTO orders(customer) VALUES ('alice'), ('bob');
+    ///      SELECT id FROM orders ORDER BY id DESC LIMIT 2",
+    /// )?;
+    /// let inserted = cursor.affected_then_rows()?;
+    /// while cursor.next_row()? {
+    ///     println!("new order {}", cursor.get_i64(0)?.unwrap());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns [`CursorError::NotAnUpdate`] if the current reply is not an
+    /// INSERT, UPDATE, DELETE or similar, and [`CursorError::NoResultSet`] if
+    /// no result set follows. Replies between the update and the result set
+    /// are skipped unless [strict mode](`Cursor::set_strict_result_sets`) is
+    /// enabled.
+    pub fn affected_then_rows(&mut self) -> CursorResult<i64> {
+        let Some(affected) = self.affected_rows().filter(|_| !self.has_result_set()) else {
+            return Err(CursorError::NotAnUpdate);
+        };
+        self.next_reply()?;
+        self.skip_to_result_set()?;
+        Ok(affected)
+    }
+
     /// Return `true` if the current reply is a result set.
     pub fn has_result_set(&self) -> bool {
         self.replies.at_result_set()
     }
 
+    /// Return the server-side id of the current result set, or `None` if the
+    /// current reply is not a result set.
+    ///
+    /// This is the id that shows up in for example
+    /// `sys.unclosed_result_sets()`.
+    pub fn result_id(&self) -> Option<u64> {
+        if let ReplyParser::Data(ResultSet { result_id, .. }) = &self.replies {
+            Some(*result_id)
+        } else {
+            None
+        }
+    }
+
+    /// Return `true` if the current result set has rows that have not yet been
+    /// sent by the server, so that iterating over it will cause more round
+    /// trips. Returns `false` if all remaining rows are buffered locally or the
+    /// current reply is not a result set.
+    ///
+    /// This can be used to decide whether to collect the whole result set in
+    /// memory or to process it row by row. Rows beyond the cap set by
+    /// [`execute_capped()`](`Cursor::execute_capped`) are not counted.
+    pub fn is_streaming(&self) -> bool {
+        let ReplyParser::Data(rs) = &self.replies else {
+            return false;
+        };
+        rs.received_rows < rs.row_limit(self.row_cap)
+    }
+
+    /// Return the complete error text sent by the server if the most recent
+    /// [`execute()`](`Cursor::execute`) failed, one line per message.
+    ///
+    /// The [`CursorError::Server`] returned by `execute()` only holds the
+    /// first message, but the server sometimes sends more, for example when
+    /// COPY INTO rejects many rows.
+    pub fn last_error_detail(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Return `true` if the underlying connection has been closed.
+    /// See [`Connection::is_closed()`](`crate::Connection::is_closed`).
+    pub fn is_closed(&self) -> bool {
+        self.conn.is_closed()
+    }
+
+    /// Return the kind of reply the cursor is currently positioned at.
+    pub fn reply_kind(&self) -> ReplyKind {
+        self.replies.kind()
+    }
+
     /// Try to move the cursor to the next reply.
     pub fn next_reply(&mut self) -> CursorResult<bool> {
         // todo: close server side result set if necessary
@@ -203,6 +803,9 @@ impl Cursor {
 
     fn switch_to_reply(&mut self, replies: ReplyParser) -> CursorResult<bool> {
         self.replies = replies;
+        if let Some(observed) = &mut self.observed {
+            observed.done_reported = false;
+        }
         let have_next = !matches!(self.replies, ReplyParser::Exhausted(..));
         Ok(have_next)
     }
@@ -234,13 +837,27 @@ impl Cursor {
     fn do_close(&mut self) -> CursorResult<()> {
         self.exhaust()?;
         let mut vec = self.replies.take_buffer();
-        self.conn.run_locked(|_state, delayed, mut sock| {
-            if !delayed.responses.is_empty() {
-                sock = delayed.send_delayed(sock)?;
-                sock = delayed.recv_delayed(sock, &mut vec)?;
+        let mut delayed_result = Ok(());
+        let ret = self
+            .conn
+            .run_locked_with(!self.try_lock, |_state, delayed, mut sock| {
+                if !delayed.responses.is_empty() {
+                    sock = delayed.send_delayed(sock)?;
+                    (sock, delayed_result) = delayed.recv_delayed(sock, &mut vec)?;
+                }
+                Ok(sock)
+            });
+        match ret {
+            // the queued commands will be sent along with the next request
+            Err(CursorError::Busy) => Ok(()),
+            Err(err) => Err(err),
+            Ok(()) => {
+                if let Err(err) = &delayed_result {
+                    self.last_error = Some(err.to_string());
+                }
+                delayed_result
             }
-            Ok(sock)
-        })
+        }
     }
 
     /// Return information about the columns of the current result set.
@@ -252,6 +869,85 @@ impl Cursor {
         }
     }
 
+    /// Return the types of the columns of the current result set. Shorthand
+    /// for mapping [`ResultColumn::sql_type()`] over
+    /// [`column_metadata()`](`Cursor::column_metadata`).
+    pub fn column_types(&self) -> Vec<MonetType> {
+        self.column_metadata()
+            .iter()
+            .map(|col| *col.sql_type())
+            .collect()
+    }
+
+    /// Return the index of the column with the given name in the current
+    /// result set.
+    ///
+    /// The name may be either the full name as returned by
+    /// [`ResultColumn::name`], for example `sys.foo.i`, or just the column
+    /// part, `i`. If several columns match, the first one is returned.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.column_metadata().iter().position(|col| {
+            let full = col.name();
+            full == name
+                || full
+                    .strip_suffix(name)
+                    .is_some_and(|table| table.ends_with('.'))
+        })
+    }
+
+    /// Return information about the columns the given query would return,
+    /// without retrieving any rows.
+    ///
+    /// The query is prepared with `PREPARE` and the prepared statement is
+    /// released again afterwards, so it must be a single statement. The
+    /// [column names](`ResultColumn::name`) have the same `table.column` form
+    /// as in a result set. The results of any earlier queries on this cursor
+    /// are discarded.
+    pub fn describe(&mut self, sql: &str) -> CursorResult<Vec<ResultColumn>> {
+        self.execute(&format!("PREPARE {sql}"))?;
+        self.skip_to_result_set()?;
+        let prepared_id = self.result_set()?.result_id;
+        // The columns are type, digits, scale, schema, table and column. The
+        // rows describing the parameters have no column name.
+        let mut columns = vec![];
+        while self.next_row()? {
+            let Some(column) = self.get_str(5)? else {
+                continue;
+            };
+            let table = self.get_str(4)?.unwrap_or_default();
+            let typ = prepared_column_type(
+                non_null(self.get_str(0)?, 0)?,
+                non_null(self.get_str(1)?, 1)?,
+                non_null(self.get_str(2)?, 2)?,
+            )?;
+            columns.push(ResultColumn::new(&format!("{table}.{column}"), typ));
+        }
+        self.conn.run_locked(|_, delayed, sock| {
+            delayed.add_xcommand("release", prepared_id);
+            Ok(sock)
+        })?;
+        self.do_close()?;
+        Ok(columns)
+    }
+
+    /// Run `EXPLAIN` on the given statement and return the plan, one line per
+    /// row of the result.
+    ///
+    /// Returns an empty string if the server sends no plan. The results of
+    /// any earlier queries on this cursor are discarded.
+    pub fn explain(&mut self, sql: &str) -> CursorResult<String> {
+        self.execute(&format!("EXPLAIN {sql}"))?;
+        let mut plan = String::new();
+        if self.has_result_set() {
+            while self.next_row()? {
+                plan.push_str(self.get_str(0)?.unwrap_or_default());
+                plan.push('\n');
+            }
+        }
+        self.do_close()?;
+        Ok(plan)
+    }
+
     /// Advance the cursor to the next available row in the result set,
     /// returning a boolean that indicates whether such a row was present.
     ///
@@ -262,25 +958,103 @@ impl Cursor {
     /// it to be *at* the first row. This means you always have to call this method
     /// before calling getters.
     pub fn next_row(&mut self) -> CursorResult<bool> {
+        self.check_cancelled()?;
         self.skip_to_result_set()?;
+        self.advance_row(usize::MAX)
+    }
 
+    /// Move to the next row like [`next_row()`](`Cursor::next_row`), but only
+    /// locate the first `nfields` fields. The others read as NULL.
+    fn advance_row(&mut self, nfields: usize) -> CursorResult<bool> {
+        let row_cap = self.row_cap;
         loop {
-            let ResultSet {
-                row_set,
-                next_row,
-                total_rows,
-                ..
-            } = self.result_set_mut();
-
-            if row_set.advance()? {
-                *next_row += 1;
+            let rs = self.result_set_mut();
+            if rs.next_row >= rs.row_limit(row_cap) {
+                let retrieved = rs.next_row;
+                self.report_result_set_done(retrieved);
+                return Ok(false);
+            }
+            if rs.row_set.advance_partial(nfields)? {
+                rs.next_row += 1;
+                self.conn.rows_fetched.fetch_add(1, Ordering::Relaxed);
                 return Ok(true);
             }
-            if next_row == total_rows {
-                return Ok(false);
+            self.fetch_more_rows()?;
+        }
+    }
+
+    fn report_result_set_done(&mut self, retrieved: u64) {
+        match &mut self.observed {
+            Some(observed) if !observed.done_reported => observed.done_reported = true,
+            _ => return,
+        }
+        self.notify_observer(QueryEventKind::ResultSetDone, None, Some(retrieved), None);
+    }
+
+    /// Advance the cursor past the next `n` rows of the result set without
+    /// decoding them, fetching more rows from the server as needed. Returns
+    /// the number of rows actually skipped, which is less than `n` if the
+    /// result set runs out.
+    ///
+    /// Afterwards the cursor is positioned *at* the last row skipped but its
+    /// fields cannot be retrieved, call [`next_row()`][`Cursor::next_row`] to
+    /// move on to the next row.
+    pub fn skip_rows(&mut self, n: u64) -> CursorResult<u64> {
+        self.check_cancelled()?;
+        self.skip_to_result_set()?;
+
+        let row_cap = self.row_cap;
+        let mut skipped = 0;
+        while skipped < n {
+            let rs = self.result_set_mut();
+            if rs.next_row >= rs.row_limit(row_cap) {
+                break;
+            }
+            if rs.row_set.skip()? {
+                rs.next_row += 1;
+                skipped += 1;
+                self.conn.rows_fetched.fetch_add(1, Ordering::Relaxed);
+                continue;
             }
             self.fetch_more_rows()?;
         }
+        Ok(skipped)
+    }
+
+    /// Low-level access to the rows as sent by the server, for example to
+    /// diagnose parsing problems.
+    ///
+    /// Returns the rows that have been received from the server but not yet
+    /// moved to, as the unparsed text of the MAPI reply: one line per row of
+    /// the form `[ 1,\t"one"\t]`, with strings still quoted and escaped. If no
+    /// such rows are available, the next batch is fetched from the server
+    /// first. Returns an empty vector at the end of the result set.
+    ///
+    /// Afterwards the cursor is positioned after the returned rows, as with
+    /// [`skip_rows()`][`Cursor::skip_rows`]. The format is an implementation
+    /// detail of the server and may change between versions.
+    pub fn raw_result_block(&mut self) -> CursorResult<Vec<u8>> {
+        self.check_cancelled()?;
+        self.skip_to_result_set()?;
+
+        let row_cap = self.row_cap;
+        let mut block = vec![];
+        loop {
+            let rs = self.result_set_mut();
+            if rs.next_row >= rs.row_limit(row_cap) {
+                break;
+            }
+            match rs.row_set.take_raw_row()? {
+                Some(row) => {
+                    block.extend_from_slice(row);
+                    rs.next_row += 1;
+                    self.conn.rows_fetched.fetch_add(1, Ordering::Relaxed);
+                }
+                None if block.is_empty() => self.fetch_more_rows()?,
+                None => break,
+            }
+        }
+        Ok(block)
     }
 
     pub(crate) fn result_set(&self) -> CursorResult<&ResultSet> {
@@ -303,32 +1077,59 @@ impl Cursor {
             match &mut self.replies {
                 ReplyParser::Data(_) => return Ok(()),
                 ReplyParser::Exhausted(_) => return Err(CursorError::NoResultSet),
+                _ if self.strict_result_sets => return Err(CursorError::NoResultSet),
                 _ => self.next_reply()?,
             };
         }
     }
 
     fn decide_next_fetch(&self) -> (u64, u64, usize) {
-        let ResultSet {
-            result_id,
-            next_row,
-            total_rows,
-            ..
-        } = self.result_set().unwrap();
-
-        let n = (total_rows - *next_row).min(self.reply_size as u64) as usize;
-        (*result_id, *next_row, n)
+        let rs = self.result_set().unwrap();
+        let remaining = rs.row_limit(self.row_cap).saturating_sub(rs.next_row);
+        let mut n = match self.reply_size {
+            0 => remaining,
+            reply_size => remaining.min(reply_size as u64),
+        };
+        if let Some(max_prefetch) = self.conn.max_prefetch {
+            n = n.min(max_prefetch as u64);
+        }
+        let n = n as usize;
+        (rs.result_id, rs.next_row, n)
     }
 
     fn fetch_more_rows(&mut self) -> CursorResult<()> {
+        self.check_cancelled()?;
         let (res_id, start, n) = self.decide_next_fetch();
         let cmd = format!("Xexport {res_id} {start} {n}");
 
         // scratch vector. TODO re-use this
         let mut vec = vec![];
 
-        // execute the command
-        self.command(&[cmd.as_bytes()], &mut vec)?;
+        // The rows of the initial reply are kept while we fetch more, so they
+        // count towards the limit.
+        let max_result_bytes = self.conn.max_result_bytes;
+        let retained = match max_result_bytes {
+            Some(_) => {
+                let rs = self.result_set_mut();
+                rs.stashed.as_ref().unwrap_or(&rs.row_set).buffer_len()
+            }
+            None => 0,
+        };
+        let limit = max_result_bytes.map(|limit| limit.saturating_sub(retained));
+
+        // execute the command. If this fails the connection is unusable and
+        // run_locked has already dropped the socket.
+        let oversized = match self.command(&[cmd.as_bytes()], &mut vec, limit) {
+            Err(CursorError::IO(e)) => return Err(CursorError::ConnectionLostMidResult(e)),
+            other => other?,
+        };
+        if let (Some(bytes), Some(limit)) = (oversized, max_result_bytes) {
+            return Err(CursorError::ResultTooLarge {
+                bytes: retained + bytes,
+                limit,
+            });
+        }
+        self.retain_block(&vec);
         ReplyParser::detect_errors(&vec)?;
 
         // parse it into a rowset
@@ -337,15 +1138,18 @@ impl Cursor {
         ReplyParser::parse_header(&mut buf, &mut fields)?;
         let ncol = fields[1];
         let mut new_row_set = RowSet::new(buf, ncol as usize);
+        new_row_set.set_quoted_columns(quoted_columns(&self.result_set_mut().columns));
 
         // If we were reading the initial response, save it.
         // Then install the new rowset, saving the old one if it's the primary.
         // We know it's the primary when stashed_primary_row_set is still None.
         let ResultSet {
             row_set,
+            received_rows,
             stashed: stashed_primary_row_set,
             ..
         } = self.result_set_mut();
+        *received_rows += fields[2];
         mem::swap(row_set, &mut new_row_set);
         if stashed_primary_row_set.is_none() {
             // new_row_set is actually the old row set now
@@ -372,6 +1176,33 @@ impl Cursor {
         Ok(Some(s))
     }
 
+    /// Like [`get_str()`](`Cursor::get_str`) but if the value is not valid
+    /// UTF-8 and [lossy mode](`Cursor::set_lossy_utf8`) is enabled, return a
+    /// copy with the invalid bytes replaced instead of an error.
+    ///
+    /// Valid values are borrowed from the cursor without copying. Like the
+    /// `&str` returned by `get_str()`, they can only be used until the cursor
+    /// moves to another row.
+    pub fn get_cow_str(&self, colnr: usize) -> CursorResult<Option<Cow<'_, str>>> {
+        let Some(field) = self.row_set()?.get_field_raw(colnr) else {
+            return Ok(None);
+        };
+        let s = if self.lossy_utf8 {
+            String::from_utf8_lossy(field)
+        } else {
+            Cow::Borrowed(from_utf8(field)?)
+        };
+        Ok(Some(s))
+    }
+
+    /// Return all values of the current row as strings, with None for NULL.
+    pub fn row_strings(&self) -> CursorResult<Vec<Option<String>>> {
+        let ncols = self.result_set()?.columns.len();
+        (0..ncols)
+            .map(|i| Ok(self.get_str(i)?.map(str::to_string)))
+            .collect()
+    }
+
     pub(crate) fn get_map<F, T>(&self, colnr: usize, f: F) -> CursorResult<Option<T>>
     where
         F: FnOnce(&[u8]) -> CursorResult<T>,
@@ -386,6 +1217,160 @@ impl Cursor {
     pub fn get<T: FromMonet>(&self, colnr: usize) -> CursorResult<Option<T>> {
         T::extract(self.result_set()?, colnr)
     }
+
+    /// Extract the whole current row using [`FromRow`], for example as a
+    /// tuple:
+    ///
+    /// ```no_run
+    /// # use monetdb::{Connection, CursorResult};
+    /// # fn f(conn: &Connection) -> CursorResult<()> {
+    /// let mut cursor = conn.cursor();
+    /// cursor.execute("SELECT id, name, score FROM players")?;
+    /// while cursor.next_row()? {
+    ///     let (id, name, score): (i32, String, Option<f64>) = cursor.get_row()?;
+    /// #   let _ = (id, name, score);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Tuples of up to 12 elements are supported. The elements are
+    /// extracted from the columns in order using [`FromMonet`], and
+    /// [`CursorError::ColumnCount`] is returned if the result set has a
+    /// different number of columns. NULL values are only allowed for elements
+    /// of type `Option<T>`.
+    pub fn get_row<T: FromRow>(&self) -> CursorResult<T> {
+        T::from_row(self)
+    }
+
+    /// Retrieve all remaining rows of the current result set and return the
+    /// values of a single column, with None for NULL.
+    ///
+    /// Starts at the row after the current one, so if
+    /// [`next_row()`][`Cursor::next_row`] hasn't been called yet the whole
+    /// column is returned. Afterwards the cursor is positioned after the
+    /// last row. Fields to the right of the column are not decoded.
+    ///
+    /// Returns [`CursorError::ColumnIndex`] if the result set has no column
+    /// `colnr`.
+    pub fn collect_column<T: FromMonet>(&mut self, colnr: usize) -> CursorResult<Vec<Option<T>>> {
+        self.check_cancelled()?;
+        self.skip_to_result_set()?;
+        let rs = self.result_set()?;
+        if colnr >= rs.columns.len() {
+            return Err(CursorError::ColumnIndex {
+                index: colnr,
+                columns: rs.columns.len(),
+            });
+        }
+
+        // Do not size the vector from the row count in the reply header, a
+        // huge result set would reserve the memory before a row is read.
+        let mut values = Vec::new();
+        while self.advance_row(colnr + 1)? {
+            values.push(T::extract(self.result_set()?, colnr)?);
+        }
+        Ok(values)
+    }
+
+    /// Retrieve all remaining rows of the current result set and return a
+    /// map from the values in column `key_col` to the values in column
+    /// `val_col`, for example to load a lookup table.
+    ///
+    /// NULL keys are an error. NULL values are an error too unless `V` is an
+    /// `Option`, see [`FromMonet`]. If a key occurs more than once, the value
+    /// from the last row wins. Like
+    /// [`collect_column()`][`Cursor::collect_column`] this starts at the row
+    /// after the current one and returns [`CursorError::ColumnIndex`] for
+    /// column indexes that are out of range.
+    pub fn collect_map<K, V>(
+        &mut self,
+        key_col: usize,
+        val_col: usize,
+    ) -> CursorResult<HashMap<K, V>>
+    where
+        K: FromMonet + Eq + Hash,
+        V: FromMonet,
+    {
+        self.check_cancelled()?;
+        self.skip_to_result_set()?;
+        let ncols = self.column_metadata().len();
+        if let Some(index) = [key_col, val_col].into_iter().find(|&c| c >= ncols) {
+            return Err(CursorError::ColumnIndex {
+                index,
+                columns: ncols,
+            });
+        }
+
+        let nfields = key_col.max(val_col) + 1;
+        let mut map = HashMap::new();
+        while self.advance_row(nfields)? {
+            let rs = self.result_set()?;
+            let key = non_null(K::extract(rs, key_col)?, key_col)?;
+            let value = non_null(V::extract(rs, val_col)?, val_col)?;
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+
+    /// Walk the current and all following replies and call `f` for each result
+    /// set, with its columns and a [`RowIter`] to retrieve its rows. Other
+    /// replies, such as updates and acknowledgements, are skipped.
+    ///
+    /// After `f` returns, the cursor moves on to the next reply, which
+    /// releases the result set on the server. Rows that `f` did not retrieve
+    /// are skipped. If `f` returns an error, the walk stops and the error is
+    /// returned.
+    ///
+    /// ```no_run
+    /// # use monetdb::Connection;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::connect_url("monetdb:///demo")?;
+    /// let mut cursor = conn.cursor();
+    /// cursor.execute("SELECT 1 AS a; UPDATE foo SET x = 1; SELECT 2 AS b, 3 AS c")?;
+    /// cursor.for_each_result_set(|columns, rows| {
+    ///     println!("{} columns", columns.len());
+    ///     while let Some(row) = rows.next_row()? {
+    ///         println!("{:?}", row.row_strings()?);
+    ///     }
+    ///     Ok(())
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_each_result_set<F>(&mut self, mut f: F) -> CursorResult<()>
+    where
+        F: FnMut(&[ResultColumn], &mut RowIter) -> CursorResult<()>,
+    {
+        loop {
+            if self.has_result_set() {
+                let columns = self.column_metadata().to_vec();
+                f(&columns, &mut RowIter { cursor: self })?;
+            }
+            if !self.next_reply()? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Retrieves the rows of a result set in
+/// [`Cursor::for_each_result_set()`].
+pub struct RowIter<'a> {
+    cursor: &'a mut Cursor,
+}
+
+impl RowIter<'_> {
+    /// Move to the next row and return the cursor so the values of the row
+    /// can be retrieved, or `None` at the end of the result set. See
+    /// [`Cursor::next_row()`].
+    pub fn next_row(&mut self) -> CursorResult<Option<&Cursor>> {
+        if self.cursor.next_row()? {
+            Ok(Some(self.cursor))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 macro_rules! define_getter {
@@ -415,6 +1400,36 @@ impl Cursor {
     define_getter!(get_usize, usize);
     define_getter!(get_f32, f32);
     define_getter!(get_f64, f64);
+
+    /// Like [`get_bool()`](`Cursor::get_bool`) but also accepts integer
+    /// columns, mapping 0 to `false` and other values to `true`. See
+    /// [`IntBool`](`crate::convert::IntBool`).
+    pub fn get_bool_lenient(&self, col: usize) -> CursorResult<Option<bool>> {
+        let value: Option<IntBool> = self.get(col)?;
+        Ok(value.map(bool::from))
+    }
+
+    /// Retrieve a DECIMAL value exactly, as the unscaled value and the scale.
+    /// The value is checked against the precision and scale of the column.
+    /// See [`ScaledDecimal`](`crate::convert::ScaledDecimal`).
+    pub fn get_scaled_decimal(&self, col: usize) -> CursorResult<Option<(i128, u8)>> {
+        let value: Option<ScaledDecimal> = self.get(col)?;
+        Ok(value.map(<(i128, u8)>::from))
+    }
+
+    /// Retrieve a DECIMAL value as the exact text sent by the server, for
+    /// example `"-12.340"` for a DECIMAL(7, 3).
+    ///
+    /// Unlike [`get_str()`](`Cursor::get_str`) this fails if the column is not
+    /// a DECIMAL column or the value does not fit its precision and scale, see
+    /// [`get_scaled_decimal()`](`Cursor::get_scaled_decimal`). Unlike
+    /// [`get_f64()`](`Cursor::get_f64`) no precision is lost.
+    pub fn get_decimal_string(&self, col: usize) -> CursorResult<Option<String>> {
+        if self.get_scaled_decimal(col)?.is_none() {
+            return Ok(None);
+        }
+        Ok(self.get_str(col)?.map(str::to_string))
+    }
 }
 
 impl Drop for Cursor {
@@ -422,3 +1437,16 @@ impl Drop for Cursor {
         let _ = self.do_close();
     }
 }
+
+/// Receive a reply into `vec`. If `limit` is given, keep at most that many
+/// bytes and return the full size of the reply if it was longer.
+fn receive_reply(
+    sock: ServerSock,
+    vec: &mut Vec<u8>,
+    limit: Option<usize>,
+) -> io::Result<(ServerSock, Option<usize>)> {
+    match limit {
+        Some(limit) => MapiReader::to_end_capped(sock, vec, limit),
+        None => Ok((MapiReader::to_end(sock, vec)?, None)),
+    }
+}
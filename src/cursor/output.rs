@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+//! Render result sets in formats meant for other tools or for humans.
+
+use std::io::{self, Write};
+
+use crate::MonetType;
+
+use super::{replies::ResultColumn, Cursor, CursorResult};
+
+impl Cursor {
+    /// Write the remaining rows of the current result set to `out` as
+    /// newline-delimited JSON, one object per row, keyed by
+    /// [`ResultColumn::name()`]. Returns the number of rows written.
+    ///
+    /// Numeric columns are written as JSON numbers and BOOLEAN columns as JSON
+    /// booleans. Everything else, including temporal and binary data, is
+    /// written as a string. NULL becomes `null`.
+    pub fn write_ndjson(&mut self, mut out: impl Write) -> CursorResult<u64> {
+        let columns = self.column_metadata().to_vec();
+        let mut line = Vec::with_capacity(200);
+        let mut count = 0;
+        while self.next_row()? {
+            line.clear();
+            let mut fields = Vec::with_capacity(columns.len());
+            for i in 0..columns.len() {
+                fields.push(self.get_str(i)?);
+            }
+            write_json_row(&mut line, &columns, &fields)?;
+            out.write_all(&line)?;
+            count += 1;
+        }
+        out.flush()?;
+        Ok(count)
+    }
+
+    /// Render the remaining rows of the current result set as an aligned
+    /// ASCII table.
+    ///
+    /// Numeric columns are aligned to the right, everything else to the left.
+    /// VARCHAR values are truncated to the declared width of the column.
+    /// All rows are collected in memory first, so this is meant for display
+    /// purposes, not for large result sets.
+    pub fn print_table(&mut self, mut out: impl Write) -> CursorResult<()> {
+        let columns = self.column_metadata().to_vec();
+        let mut rows = vec![];
+        while self.next_row()? {
+            let mut row = Vec::with_capacity(columns.len());
+            for i in 0..columns.len() {
+                row.push(self.get_str(i)?.map(str::to_string));
+            }
+            rows.push(row);
+        }
+        write_table(&mut out, &columns, &rows)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+fn write_json_row(
+    out: &mut Vec<u8>,
+    columns: &[ResultColumn],
+    fields: &[Option<&str>],
+) -> io::Result<()> {
+    out.push(b'{');
+    for (i, (col, field)) in columns.iter().zip(fields).enumerate() {
+        if i > 0 {
+            out.push(b',');
+        }
+        write_json_string(out, col.name())?;
+        out.push(b':');
+        let typ = col.sql_type();
+        match field {
+            None => out.write_all(b"null")?,
+            Some(s) if typ.is_numeric() || typ.is_bool() => out.write_all(s.as_bytes())?,
+            Some(s) => write_json_string(out, s)?,
+        }
+    }
+    out.write_all(b"}\n")
+}
+
+fn write_table(
+    out: &mut impl Write,
+    columns: &[ResultColumn],
+    rows: &[Vec<Option<String>>],
+) -> io::Result<()> {
+    let cell = |col: &ResultColumn, value: &Option<String>| -> String {
+        let Some(value) = value else {
+            return "null".to_string();
+        };
+        match col.sql_type() {
+            MonetType::Varchar(w @ 1..) => value.chars().take(*w as usize).collect(),
+            _ => value.clone(),
+        }
+    };
+
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.name().chars().count()).collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .zip(row)
+                .zip(&mut widths)
+                .map(|((col, value), width)| {
+                    let text = cell(col, value);
+                    *width = (*width).max(text.chars().count());
+                    text
+                })
+                .collect()
+        })
+        .collect();
+
+    let separator = |out: &mut dyn Write, fill: char| -> io::Result<()> {
+        for w in &widths {
+            write!(out, "+{}", fill.to_string().repeat(w + 2))?;
+        }
+        writeln!(out, "+")
+    };
+
+    separator(out, '-')?;
+    for (col, w) in columns.iter().zip(&widths) {
+        write!(out, "| {:<w$} ", col.name())?;
+    }
+    writeln!(out, "|")?;
+    separator(out, '=')?;
+    for row in &cells {
+        for ((col, text), w) in columns.iter().zip(row).zip(&widths) {
+            if col.sql_type().is_numeric() {
+                write!(out, "| {text:>w$} ")?;
+            } else {
+                write!(out, "| {text:<w$} ")?;
+            }
+        }
+        writeln!(out, "|")?;
+    }
+    separator(out, '-')?;
+    Ok(())
+}
+
+fn write_json_string(out: &mut Vec<u8>, s: &str) -> io::Result<()> {
+    out.push(b'"');
+    for c in s.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            '\u{0}'..='\u{1f}' => write!(out, "\\u{:04x}", c as u32)?,
+            _ => {
+                let mut buf = [0u8; 4];
+                out.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+            }
+        }
+    }
+    out.push(b'"');
+    Ok(())
+}
+
+#[test]
+fn test_write_json_row() {
+    let columns = [
+        ResultColumn::new("t.i", MonetType::Int),
+        ResultColumn::new("t.b", MonetType::Bool),
+        ResultColumn::new("t.d", MonetType::Decimal(5, 2)),
+        ResultColumn::new("t.s", MonetType::Varchar(10)),
+        ResultColumn::new("t.ts", MonetType::Timestamp(3)),
+    ];
+
+    let mut out = vec![];
+    let fields = [
+        Some("42"),
+        Some("true"),
+        Some("-1.50"),
+        Some("a\"b\\c\td\u{1}"),
+        Some("2024-10-16 12:34:56.789"),
+    ];
+    write_json_row(&mut out, &columns, &fields).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        concat!(
+            r#"{"t.i":42,"t.b":true,"t.d":-1.50,"t.s":"a\"b\\c\td\u0001","#,
+            r#""t.ts":"2024-10-16 12:34:56.789"}"#,
+            "\n"
+        )
+    );
+
+    let mut out = vec![];
+    write_json_row(&mut out, &columns, &[None; 5]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "{\"t.i\":null,\"t.b\":null,\"t.d\":null,\"t.s\":null,\"t.ts\":null}\n"
+    );
+
+    let mut out = vec![];
+    write_json_row(&mut out, &[], &[]).unwrap();
+    assert_eq!(out, b"{}\n");
+}
+
+#[test]
+fn test_write_table() {
+    let columns = [
+        ResultColumn::new("t.i", MonetType::Int),
+        ResultColumn::new("t.name", MonetType::Varchar(5)),
+    ];
+    let rows = [
+        vec![Some("1".to_string()), Some("one".to_string())],
+        vec![Some("-1000".to_string()), None],
+        vec![None, Some("truncated".to_string())],
+    ];
+
+    let mut out = vec![];
+    write_table(&mut out, &columns, &rows).unwrap();
+    let expected = "\
++-------+--------+
+| t.i   | t.name |
++=======+========+
+|     1 | one    |
+| -1000 | null   |
+|  null | trunc  |
++-------+--------+
+";
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+//! Convert result sets to Apache Arrow record batches.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{
+        ArrayRef, BinaryBuilder, BooleanBuilder, Date32Builder, Decimal128Builder, Float32Builder,
+        Float64Builder, Int16Builder, Int32Builder, Int64Builder, Int8Builder, StringBuilder,
+        TimestampMicrosecondBuilder,
+    },
+    datatypes::{Field, Schema},
+    record_batch::RecordBatch,
+};
+
+use crate::{
+    convert::{
+        conversion_error, conversion_error_from, from_utf8,
+        raw_decimal::RawDecimal,
+        raw_temporal::{RawDate, RawTime, RawTimestamp, RawTimestampTz},
+        FromMonet,
+    },
+    monettypes::{Precision, Scale},
+    MonetType,
+};
+
+use super::{replies::ResultSet, Cursor, CursorResult};
+
+impl Cursor {
+    /// Retrieve the remaining rows of the current result set as an Arrow
+    /// [`RecordBatch`]. Requires the `arrow` feature.
+    ///
+    /// The fields of the batch are named after the column part of
+    /// [`ResultColumn::name()`](`super::replies::ResultColumn::name`) and are
+    /// all nullable, NULL values are marked in the validity bitmap. The column
+    /// types are mapped as follows:
+    ///
+    /// | MonetDB                          | Arrow                              |
+    /// |----------------------------------|------------------------------------|
+    /// | BOOLEAN                          | Boolean                            |
+    /// | TINYINT, SMALLINT, INT, BIGINT   | Int8, Int16, Int32, Int64          |
+    /// | HUGEINT                          | Decimal128(38, 0)                  |
+    /// | DECIMAL(p, s)                    | Decimal128(p, s)                   |
+    /// | REAL, DOUBLE                     | Float32, Float64                   |
+    /// | INTERVAL MONTH                   | Int32, the number of months        |
+    /// | DATE                             | Date32                             |
+    /// | TIMESTAMP                        | Timestamp(Microsecond, None)       |
+    /// | TIMESTAMP WITH TIME ZONE         | Timestamp(Microsecond, "+00:00")   |
+    /// | BLOB                             | Binary                             |
+    ///
+    /// All other types, for example VARCHAR, TIME, the other INTERVAL types,
+    /// UUID, INET, JSON and OID, become Utf8 holding the text sent by the
+    /// server. All rows are collected in memory.
+    pub fn to_record_batch(&mut self) -> CursorResult<RecordBatch> {
+        self.skip_to_result_set()?;
+        let columns = self.column_metadata().to_vec();
+        let mut builders = columns
+            .iter()
+            .map(|col| ColumnBuilder::new(col.sql_type()))
+            .collect::<CursorResult<Vec<_>>>()?;
+
+        while self.next_row()? {
+            let rs = self.result_set()?;
+            for (colnr, builder) in builders.iter_mut().enumerate() {
+                builder.append(rs, colnr)?;
+            }
+        }
+
+        let mut fields = Vec::with_capacity(columns.len());
+        let mut arrays = Vec::with_capacity(columns.len());
+        for (col, mut builder) in columns.iter().zip(builders) {
+            let array = builder.finish();
+            let name = col.name();
+            let name = name.rsplit_once('.').map_or(name, |(_, column)| column);
+            fields.push(Field::new(name, array.data_type().clone(), true));
+            arrays.push(array);
+        }
+        let schema = Arc::new(Schema::new(fields));
+        RecordBatch::try_new(schema, arrays).map_err(conversion_error_from::<RecordBatch>)
+    }
+}
+
+enum ColumnBuilder {
+    Bool(BooleanBuilder),
+    Int8(Int8Builder),
+    Int16(Int16Builder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    Decimal(Decimal128Builder, Scale),
+    Date(Date32Builder),
+    Timestamp(TimestampMicrosecondBuilder),
+    TimestampTz(TimestampMicrosecondBuilder),
+    Binary(BinaryBuilder),
+    Utf8(StringBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(typ: &MonetType) -> CursorResult<Self> {
+        let builder = match *typ {
+            MonetType::Bool => Self::Bool(BooleanBuilder::new()),
+            MonetType::TinyInt => Self::Int8(Int8Builder::new()),
+            MonetType::SmallInt => Self::Int16(Int16Builder::new()),
+            MonetType::Int | MonetType::MonthInterval => Self::Int32(Int32Builder::new()),
+            MonetType::BigInt => Self::Int64(Int64Builder::new()),
+            MonetType::HugeInt => Self::decimal(38, 0)?,
+            MonetType::Decimal(precision, scale) => Self::decimal(precision, scale)?,
+            MonetType::Real => Self::Float32(Float32Builder::new()),
+            MonetType::Double => Self::Float64(Float64Builder::new()),
+            MonetType::Date => Self::Date(Date32Builder::new()),
+            MonetType::Timestamp(_) => Self::Timestamp(TimestampMicrosecondBuilder::new()),
+            MonetType::TimestampTz(_) => {
+                Self::TimestampTz(TimestampMicrosecondBuilder::new().with_timezone("+00:00"))
+            }
+            MonetType::Blob => Self::Binary(BinaryBuilder::new()),
+            _ => Self::Utf8(StringBuilder::new()),
+        };
+        Ok(builder)
+    }
+
+    fn decimal(precision: Precision, scale: Scale) -> CursorResult<Self> {
+        let builder = Decimal128Builder::new()
+            .with_precision_and_scale(precision, scale as i8)
+            .map_err(conversion_error_from::<RecordBatch>)?;
+        Ok(Self::Decimal(builder, scale))
+    }
+
+    fn append(&mut self, rs: &ResultSet, colnr: usize) -> CursorResult<()> {
+        match self {
+            Self::Bool(b) => b.append_option(bool::extract(rs, colnr)?),
+            Self::Int8(b) => b.append_option(i8::extract(rs, colnr)?),
+            Self::Int16(b) => b.append_option(i16::extract(rs, colnr)?),
+            Self::Int32(b) => b.append_option(i32::extract(rs, colnr)?),
+            Self::Int64(b) => b.append_option(i64::extract(rs, colnr)?),
+            Self::Float32(b) => b.append_option(f32::extract(rs, colnr)?),
+            Self::Float64(b) => b.append_option(f64::extract(rs, colnr)?),
+            Self::Decimal(b, scale) => {
+                let value = match RawDecimal::<i128>::extract(rs, colnr)? {
+                    Some(dec) => Some(dec.at_scale(*scale).ok_or_else(|| {
+                        conversion_error::<RawDecimal<i128>>("too many decimal digits")
+                    })?),
+                    None => None,
+                };
+                b.append_option(value)
+            }
+            Self::Date(b) => b.append_option(RawDate::extract(rs, colnr)?.map(days_since_epoch)),
+            Self::Timestamp(b) => {
+                let value = RawTimestamp::extract(rs, colnr)?;
+                b.append_option(value.map(|ts| micros_since_epoch(ts.date, ts.time)))
+            }
+            Self::TimestampTz(b) => {
+                let value = RawTimestampTz::extract(rs, colnr)?;
+                b.append_option(value.map(|ts| {
+                    micros_since_epoch(ts.date, ts.time) - ts.tz.seconds_east as i64 * 1_000_000
+                }))
+            }
+            Self::Binary(b) => b.append_option(Vec::<u8>::extract(rs, colnr)?),
+            Self::Utf8(b) => match rs.row_set.get_field_raw(colnr) {
+                Some(field) => b.append_value(from_utf8(field)?),
+                None => b.append_null(),
+            },
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Bool(b) => Arc::new(b.finish()),
+            Self::Int8(b) => Arc::new(b.finish()),
+            Self::Int16(b) => Arc::new(b.finish()),
+            Self::Int32(b) => Arc::new(b.finish()),
+            Self::Int64(b) => Arc::new(b.finish()),
+            Self::Float32(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+            Self::Decimal(b, _) => Arc::new(b.finish()),
+            Self::Date(b) => Arc::new(b.finish()),
+            Self::Timestamp(b) => Arc::new(b.finish()),
+            Self::TimestampTz(b) => Arc::new(b.finish()),
+            Self::Binary(b) => Arc::new(b.finish()),
+            Self::Utf8(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Number of days between 1970-01-01 and the given date in the proleptic
+/// Gregorian calendar.
+fn days_since_epoch(date: RawDate) -> i32 {
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let month = date.month as i32;
+    let day = date.day as i32;
+    let year = date.year as i32 - (month <= 2) as i32;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn micros_since_epoch(date: RawDate, time: RawTime) -> i64 {
+    let days = days_since_epoch(date) as i64;
+    let minutes = (days * 24 + time.hours as i64) * 60 + time.minutes as i64;
+    minutes * 60_000_000 + time.microseconds() as i64
+}
+
+#[test]
+fn test_days_since_epoch() {
+    let days = |year, month, day| days_since_epoch(RawDate { day, month, year });
+    assert_eq!(days(1970, 1, 1), 0);
+    assert_eq!(days(1970, 1, 2), 1);
+    assert_eq!(days(1969, 12, 31), -1);
+    assert_eq!(days(2000, 3, 1), 11_017);
+    assert_eq!(days(2024, 2, 29), 19_782);
+    assert_eq!(days(1600, 1, 1), -135_140);
+
+    let time = RawTime {
+        microseconds: 500_000,
+        seconds: 59,
+        minutes: 32,
+        hours: 10,
+    };
+    let date = RawDate {
+        day: 2,
+        month: 1,
+        year: 1970,
+    };
+    assert_eq!(
+        micros_since_epoch(date, time),
+        ((24 * 60 + 10 * 60 + 32) * 60 + 59) * 1_000_000 + 500_000
+    );
+}
@@ -8,11 +8,12 @@
 
 #![allow(dead_code)]
 
-use std::{error, iter, mem, str::FromStr};
+use std::{any::Any, error, mem, str::FromStr};
 
 use bstr::{BStr, BString, ByteSlice};
 use memchr::memmem;
 
+use crate::convert::{self, conversion_error_from};
 use crate::monettypes::MonetType;
 
 use super::{rowset::RowSet, CursorError, CursorResult};
@@ -39,6 +40,8 @@ pub enum BadReply {
     InvalidBackslashEscape,
     #[error("column index {0} out of bounds, have only {1} columns")]
     ColumnIndexOutOfBounds(usize, usize),
+    #[error("unquoted value in string column {0}")]
+    UnquotedString(usize),
 }
 
 pub type RResult<T> = Result<T, BadReply>;
@@ -58,6 +61,11 @@ impl ReplyBuf {
         self.data
     }
 
+    /// The size of the whole buffer, including what has been consumed.
+    pub fn total_len(&self) -> usize {
+        self.data.len()
+    }
+
     pub fn mut_vec(&mut self) -> &mut Vec<u8> {
         &mut self.data
     }
@@ -165,6 +173,25 @@ impl ReplyBuf {
                         }
                         (e1 << 6) | (e2 << 3) | e3
                     }
+                    b'u' => {
+                        // \uXXXX, used by newer servers for example for the
+                        // C1 control characters. Always shorter than its
+                        // UTF-8 encoding so wr stays behind rd.
+                        if avail < 5 {
+                            return Err(BadReply::UnexpectedEnd);
+                        }
+                        let hex = unsafe { std::slice::from_raw_parts(rd, 4) };
+                        rd = rd.wrapping_add(4);
+                        let Some(chr) = decode_hex_char(hex) else {
+                            return Err(BadReply::InvalidBackslashEscape);
+                        };
+                        let mut utf8 = [0u8; 4];
+                        for &byte in chr.encode_utf8(&mut utf8).as_bytes() {
+                            unsafe { wr.write(byte) };
+                            wr = wr.wrapping_add(1);
+                        }
+                        continue;
+                    }
                     _ => return Err(BadReply::InvalidBackslashEscape),
                 }
             } else if b == b'"' {
@@ -189,6 +216,15 @@ impl ReplyBuf {
     }
 }
 
+/// Decode the four hex digits of a `\uXXXX` escape.
+fn decode_hex_char(hex: &[u8]) -> Option<char> {
+    if !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let hex = std::str::from_utf8(hex).ok()?;
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
 #[test]
 fn test_convert_backslashes() {
     #[track_caller]
@@ -239,10 +275,62 @@ fn test_convert_backslashes() {
     // LATIN SMALL LETTER A WITH DIAERESIS == \u{e4} == oct utf-8 \303\244
     f(r#"foo"b\303\244nana""#, 0, Ok("b\u{e4}nana"));
 
+    // backslashes, quotes and the octal escape of every byte value
+    f(r#"foo"a\\b\\""#, 0, Ok("a\\b\\"));
+    f(r#"foo"\"\"\"""#, 0, Ok("\"\"\""));
+    f(r#"foo"a\"#, 0, Err(BadReply::InvalidBackslashEscape));
+    for byte in 0..=255u8 {
+        let data = format!("foo\"x\\{byte:03o}y\"");
+        let mut buf = ReplyBuf::new(data.into());
+        buf.consume(4);
+        assert_eq!(buf.convert_backslashes(0).unwrap(), [b'x', byte, b'y']);
+    }
+    f(r#"foo"\400""#, 0, Err(BadReply::InvalidBackslashEscape));
+    f(r#"foo"\08""#, 0, Err(BadReply::InvalidBackslashEscape));
+    f(r#"foo"\01"#, 0, Err(BadReply::UnexpectedEnd));
+    // SMILEY == \u{263A} == oct utf-8 \342\230\272
+    f(r#"foo"\342\230\272""#, 0, Ok("\u{263A}"));
+
+    // unicode escapes
+    f(r#"foo"a\u0085b""#, 0, Ok("a\u{85}b"));
+    f(r#"foo"\u2028\u00E4""#, 0, Ok("\u{2028}\u{e4}"));
+    f(r#"foo"\u0041""#, 0, Ok("A"));
+    f(r#"foo"\u00zz""#, 0, Err(BadReply::InvalidBackslashEscape));
+    f(r#"foo"\u+041""#, 0, Err(BadReply::InvalidBackslashEscape));
+    f(r#"foo"\uD800""#, 0, Err(BadReply::InvalidBackslashEscape));
+    f(r#"foo"\u004"#, 0, Err(BadReply::UnexpectedEnd));
+
+    // \b and \v are sent as octal escapes
+    f(r#"foo"\b""#, 0, Err(BadReply::InvalidBackslashEscape));
+    f(r#"foo"\010\013""#, 0, Ok("\u{8}\u{b}"));
+
     // Test the skip. 4 skips the bana but it's still included in the result
     f(r#"foo"bana\na""#, 4, Ok("bana\na"));
 }
 
+/// The kind of reply a [`Cursor`](`crate::Cursor`) is positioned at, as
+/// returned by [`Cursor::reply_kind()`](`crate::Cursor::reply_kind`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyKind {
+    /// A result set, retrieve the rows with
+    /// [`next_row()`](`crate::Cursor::next_row`).
+    ResultSet,
+    /// An INSERT, UPDATE or DELETE, see
+    /// [`affected_rows()`](`crate::Cursor::affected_rows`).
+    Update,
+    /// A statement that succeeded without further information, for example
+    /// CREATE TABLE.
+    Other,
+    /// A change of transaction state, for example START TRANSACTION or
+    /// COMMIT.
+    Transaction,
+    /// The statement failed. The error has already been returned by
+    /// [`execute()`](`crate::Cursor::execute`).
+    Error,
+    /// There are no more replies.
+    Exhausted,
+}
+
 #[derive(Debug)]
 pub enum ReplyParser {
     Exhausted(Vec<u8>),
@@ -258,15 +346,95 @@ pub enum ReplyParser {
     },
 }
 
+/// The current row of a result set, as passed to
+/// [`FromMonet::extract()`](`crate::convert::FromMonet::extract`).
+///
+/// Implementations of `FromMonet` outside this crate use
+/// [`field()`](`ResultSet::field`) to access the data.
 #[derive(Debug)]
 pub struct ResultSet {
-    pub result_id: u64,
-    pub next_row: u64,
-    pub total_rows: u64,
-    pub columns: Vec<ResultColumn>,
-    pub row_set: RowSet,
-    pub stashed: Option<RowSet>,
-    pub to_close: Option<u64>,
+    pub(crate) result_id: u64,
+    pub(crate) next_row: u64,
+    pub(crate) total_rows: u64,
+    /// Number of rows sent by the server so far, both in the initial reply
+    /// and in response to Xexport.
+    pub(crate) received_rows: u64,
+    pub(crate) columns: Vec<ResultColumn>,
+    pub(crate) row_set: RowSet,
+    pub(crate) stashed: Option<RowSet>,
+    pub(crate) to_close: Option<u64>,
+}
+
+impl ResultSet {
+    /// The number of rows the cursor will move through: all of them, or at
+    /// most `row_cap` if the query was executed with
+    /// [`execute_capped()`](`crate::Cursor::execute_capped`).
+    pub(crate) fn row_limit(&self, row_cap: Option<u64>) -> u64 {
+        match row_cap {
+            Some(cap) => cap.min(self.total_rows),
+            None => self.total_rows,
+        }
+    }
+
+    /// Return the columns of the result set.
+    pub fn columns(&self) -> &[ResultColumn] {
+        &self.columns
+    }
+
+    /// Return the given field of the current row, or `None` if it is NULL or
+    /// there is no such column.
+    pub fn field(&self, colnr: usize) -> Option<FieldBytes<'_>> {
+        let bytes = self.row_set.get_field_raw(colnr)?;
+        let sql_type = self.columns[colnr].sql_type();
+        Some(FieldBytes { bytes, sql_type })
+    }
+}
+
+/// A non-NULL field of a result set together with the type of its column,
+/// see [`ResultSet::field()`].
+///
+/// The bytes are the text sent by the server, with the quotes and escapes of
+/// string values already removed.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldBytes<'a> {
+    bytes: &'a [u8],
+    sql_type: &'a MonetType,
+}
+
+impl<'a> FieldBytes<'a> {
+    /// Return the raw bytes of the field.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Return the field as a string, or [`CursorError::BadReply`] if it is
+    /// not valid UTF-8.
+    pub fn as_str(&self) -> CursorResult<&'a str> {
+        convert::from_utf8(self.bytes)
+    }
+
+    /// Return the type of the column the field belongs to.
+    pub fn sql_type(&self) -> &'a MonetType {
+        self.sql_type
+    }
+
+    /// Parse the field using [`FromStr`]. Parse errors are reported as
+    /// [`CursorError::Conversion`].
+    pub fn parse<T>(&self) -> CursorResult<T>
+    where
+        T: FromStr + Any,
+        <T as FromStr>::Err: error::Error + Send + Sync + 'static,
+    {
+        self.as_str()?.parse().map_err(conversion_error_from::<T>)
+    }
+}
+
+/// For each column, whether its values must be quoted.
+pub(crate) fn quoted_columns(columns: &[ResultColumn]) -> Vec<bool> {
+    columns
+        .iter()
+        .map(|col| col.sql_type().is_quoted_in_text())
+        .collect()
 }
 
 impl Default for ReplyParser {
@@ -303,6 +471,19 @@ impl ReplyParser {
         }
     }
 
+    pub fn kind(&self) -> ReplyKind {
+        match self {
+            ReplyParser::Exhausted(_) => ReplyKind::Exhausted,
+            ReplyParser::Error(_) => ReplyKind::Error,
+            ReplyParser::Success {
+                affected: Some(_), ..
+            } => ReplyKind::Update,
+            ReplyParser::Success { affected: None, .. } => ReplyKind::Other,
+            ReplyParser::Data(_) => ReplyKind::ResultSet,
+            ReplyParser::Tx { .. } => ReplyKind::Transaction,
+        }
+    }
+
     pub fn at_result_set(&self) -> bool {
         matches!(self, ReplyParser::Data { .. })
     }
@@ -355,6 +536,47 @@ impl ReplyParser {
         Err(CursorError::Server(message.to_string()))
     }
 
+    /// Find the result sets the server keeps open in the beginning of a
+    /// response that has been cut off, see
+    /// [`Parameters::set_max_result_bytes()`](`crate::Parameters::set_max_result_bytes`).
+    /// Only complete header lines that include the number of rows sent are
+    /// considered.
+    pub(crate) fn open_result_ids(prefix: &[u8]) -> Vec<u64> {
+        let Some(end) = prefix.rfind_byte(b'\n') else {
+            return vec![];
+        };
+        prefix[..end]
+            .lines()
+            .filter_map(|line| {
+                let header = std::str::from_utf8(line.strip_prefix(b"&1 ")?).ok()?;
+                let fields: Option<Vec<u64>> = header
+                    .split_ascii_whitespace()
+                    .map(|f| f.parse().ok())
+                    .collect();
+                match fields?[..] {
+                    [id, total, _ncols, included, ..] if included < total => Some(id),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Collect the lines of the first error block in the response, without
+    /// their leading `!`.
+    pub fn error_detail(response: &[u8]) -> Option<String> {
+        let start = if response.first() == Some(&b'!') {
+            0
+        } else {
+            memmem::find(response, b"\n!")? + 1
+        };
+        let lines: Vec<_> = response[start..]
+            .lines()
+            .map_while(|line| line.strip_prefix(b"!"))
+            .map(|line| String::from_utf8_lossy(line))
+            .collect();
+        Some(lines.join("\n"))
+    }
+
     fn parse(buf: ReplyBuf) -> RResult<ReplyParser> {
         let ahead = buf.peek();
         match ahead {
@@ -363,7 +585,8 @@ impl ReplyParser {
                 vec.clear();
                 Ok(ReplyParser::Exhausted(vec))
             }
-            [b'&', b'1', ..] => Self::parse_data(buf),
+            // the reply to PREPARE is a result set with the same layout
+            [b'&', b'1' | b'5', ..] => Self::parse_data(buf),
             [b'&', b'2', ..] => Self::parse_successful_update(buf),
             [b'&', b'3', ..] => Self::parse_successful_other(buf),
             [b'&', b'4', ..] => Self::parse_autocommit_status(buf),
@@ -394,13 +617,26 @@ impl ReplyParser {
     }
 
     pub(crate) fn parse_header<T: FromStr>(buf: &mut ReplyBuf, dest: &mut [T]) -> RResult<()> {
+        Self::parse_header_items(buf, dest, dest.len())?;
+        Ok(())
+    }
+
+    /// Like [`parse_header`](Self::parse_header) but only the first `required`
+    /// items must be present. Returns the number of items found.
+    fn parse_header_items<T: FromStr>(
+        buf: &mut ReplyBuf,
+        dest: &mut [T],
+        required: usize,
+    ) -> RResult<usize> {
         let line = buf.split_str(b'\n', "header line")?.trim_ascii();
         let mut parts = line[3..].split(' ');
         for (i, d) in dest.iter_mut().enumerate() {
             let Some(p) = parts.next() else {
+                if i >= required {
+                    return Ok(i);
+                }
                 return Err(BadReply::InvalidHeader(format!(
-                    "not enough header items, expected {n}: {line}",
-                    n = dest.len()
+                    "not enough header items, expected {required}: {line}"
                 )));
             };
             let Ok(value) = p.parse() else {
@@ -410,7 +646,7 @@ impl ReplyParser {
             };
             *d = value;
         }
-        Ok(())
+        Ok(dest.len())
     }
 
     fn parse_autocommit_status(mut buf: ReplyBuf) -> RResult<ReplyParser> {
@@ -434,17 +670,17 @@ impl ReplyParser {
     }
 
     fn parse_data(mut buf: ReplyBuf) -> RResult<ReplyParser> {
+        // Servers that do not send size headers omit the fourth field,
+        // the number of rows included in this reply.
         let mut fields = [0; 4];
-        Self::parse_header(&mut buf, &mut fields)?;
+        let nfields = Self::parse_header_items(&mut buf, &mut fields, 3)?;
         let [result_id, rows_total, ncols, rows_included] = fields;
         if ncols > usize::MAX as u64 {
             return Err(BadReply::TooManyColumns(ncols));
         }
         let ncols = ncols as usize;
-        let to_close = (rows_included < rows_total).then_some(result_id);
 
-        let mut columns: Vec<ResultColumn> =
-            iter::repeat(ResultColumn::empty()).take(ncols).collect();
+        let mut columns: Vec<ResultColumn> = vec![ResultColumn::empty(); ncols];
 
         // parse the table_name header
         Self::parse_data_header(&mut buf, "table_name", &mut columns, &|col, s| {
@@ -468,31 +704,45 @@ impl ReplyParser {
             Ok(())
         })?;
 
-        // parse the length header
-        Self::parse_data_header(&mut buf, "length", &mut columns, &|col, s| {
-            if let MonetType::Varchar(n) = &mut col.typ {
-                *n = u32::from_str(s)?
-            };
-            Ok(())
-        })?;
+        // parse the length header, if present
+        if Self::next_data_header_is(&buf, "length") {
+            Self::parse_data_header(&mut buf, "length", &mut columns, &|col, s| {
+                if let MonetType::Varchar(n) = &mut col.typ {
+                    *n = u32::from_str(s)?
+                };
+                Ok(())
+            })?;
+        }
 
-        // parse the typesizes header
-        Self::parse_data_header(&mut buf, "typesizes", &mut columns, &|col, s| {
-            if let MonetType::Decimal(precision, scale) = &mut col.typ {
-                let Some((pr, sc)) = s.split_once(' ') else {
+        // parse the typesizes header, if present
+        if Self::next_data_header_is(&buf, "typesizes") {
+            Self::parse_data_header(&mut buf, "typesizes", &mut columns, &|col, s| {
+                let Some((digits, scale)) = s.split_once(' ') else {
                     return Err("expect typesizes to be PRECISION <space> SCALE".into());
                 };
-                *precision = pr.parse()?;
-                *scale = sc.parse()?;
-            };
-            Ok(())
-        })?;
+                apply_type_sizes(&mut col.typ, digits, scale)
+            })?;
+        }
 
-        let row_set = RowSet::new(buf, columns.len());
+        let rows_included = if nfields == 4 {
+            rows_included
+        } else {
+            Self::count_row_lines(&buf)
+        };
+        if rows_included > rows_total {
+            return Err(BadReply::InvalidHeader(format!(
+                "result set {result_id} includes {rows_included} rows but has only {rows_total}"
+            )));
+        }
+        let to_close = (rows_included < rows_total).then_some(result_id);
+
+        let mut row_set = RowSet::new(buf, columns.len());
+        row_set.set_quoted_columns(quoted_columns(&columns));
         Ok(ReplyParser::Data(ResultSet {
             result_id,
             next_row: 0,
             total_rows: rows_total,
+            received_rows: rows_included,
             columns,
             row_set,
             to_close,
@@ -500,6 +750,24 @@ impl ReplyParser {
         }))
     }
 
+    /// Check whether the next line is a data header of the given kind.
+    fn next_data_header_is(buf: &ReplyBuf, kind: &str) -> bool {
+        let data = buf.peek();
+        let line = match memchr::memchr(b'\n', data) {
+            Some(n) => &data[..n],
+            None => data,
+        };
+        line.starts_with(b"% ") && line.ends_with(format!(" # {kind}").as_bytes())
+    }
+
+    /// Count the consecutive row lines at the start of the buffer.
+    fn count_row_lines(buf: &ReplyBuf) -> u64 {
+        buf.peek()
+            .split(|&b| b == b'\n')
+            .take_while(|line| line.starts_with(b"["))
+            .count() as u64
+    }
+
     fn parse_data_header<'a>(
         buf: &'a mut ReplyBuf,
         expected_kind: &str,
@@ -573,6 +841,53 @@ impl ResultColumn {
     }
 }
 
+/// Fill in the precision of decimal and temporal types from the digits and
+/// scale the server reports next to the type name, in the `typesizes` header
+/// or in the output of `PREPARE`.
+fn apply_type_sizes(
+    typ: &mut MonetType,
+    digits: &str,
+    scale: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    match typ {
+        MonetType::Decimal(precision, sc) => {
+            *precision = digits.parse()?;
+            *sc = scale.parse()?;
+        }
+        // For the temporal types the server sends the number of
+        // decimals plus one
+        MonetType::Time(decimals)
+        | MonetType::TimeTz(decimals)
+        | MonetType::Timestamp(decimals)
+        | MonetType::TimestampTz(decimals) => {
+            let digits: u8 = digits.parse()?;
+            *decimals = digits.saturating_sub(1);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Determine the type of a result column from a row of `PREPARE` output.
+/// For VARCHAR the digits are the declared width.
+pub(crate) fn prepared_column_type(
+    type_name: &str,
+    digits: &str,
+    scale: &str,
+) -> RResult<MonetType> {
+    let invalid = |e: Box<dyn error::Error>| {
+        BadReply::InvalidHeader(format!("PREPARE type {type_name}({digits},{scale}): {e}"))
+    };
+    let Some(mut typ) = MonetType::prototype(type_name) else {
+        return Err(invalid(format!("unknown column type: {type_name}").into()));
+    };
+    if let MonetType::Varchar(width) = &mut typ {
+        *width = digits.parse().map_err(|e| invalid(Box::new(e)))?;
+    }
+    apply_type_sizes(&mut typ, digits, scale).map_err(invalid)?;
+    Ok(typ)
+}
+
 type ResultColumnUpdater<'x, 'a> =
     &'x dyn Fn(&'a mut ResultColumn, &'a str) -> Result<(), Box<dyn error::Error>>;
 
@@ -582,3 +897,126 @@ pub fn from_utf8<'a>(context: &'static str, bytes: &'a [u8]) -> RResult<&'a str>
         Err(_) => Err(BadReply::Unicode(context)),
     }
 }
+
+#[test]
+fn test_reply_kind() {
+    let kind = |reply: &str| ReplyParser::new(reply.as_bytes().to_vec()).unwrap().kind();
+    assert_eq!(kind(""), ReplyKind::Exhausted);
+    assert_eq!(kind("!42000!syntax error\n"), ReplyKind::Error);
+    assert_eq!(kind("&2 3 -1\n"), ReplyKind::Update);
+    assert_eq!(kind("&3 12 1\n"), ReplyKind::Other);
+    assert_eq!(kind("&4 f\n"), ReplyKind::Transaction);
+    let data = "&1 0 1 1 0\n% .x # table_name\n% x # name\n% int # type\n% 1 # length\n% 0 0 # typesizes\n";
+    assert_eq!(kind(data), ReplyKind::ResultSet);
+    let prepared =
+        "&5 7 1 1 1\n% .prepare # table_name\n% type # name\n% varchar # type\n[ \"int\"\t]\n";
+    assert_eq!(kind(prepared), ReplyKind::ResultSet);
+}
+
+#[test]
+fn test_prepared_column_type() {
+    let typ = |t, d, s| prepared_column_type(t, d, s).unwrap();
+    assert_eq!(typ("int", "32", "0"), MonetType::Int);
+    assert_eq!(typ("varchar", "10", "0"), MonetType::Varchar(10));
+    assert_eq!(typ("decimal", "8", "3"), MonetType::Decimal(8, 3));
+    assert_eq!(typ("timestamp", "4", "0"), MonetType::Timestamp(3));
+    assert!(prepared_column_type("nonsense", "0", "0").is_err());
+    assert!(prepared_column_type("decimal", "x", "0").is_err());
+}
+
+#[test]
+fn test_error_detail() {
+    assert_eq!(ReplyParser::error_detail(b"&2 1 -1\n"), None);
+    let response = b"&2 1 -1\n!22003!value too large\n!42000!row 2 rejected\n&3 1\n";
+    assert_eq!(
+        ReplyParser::error_detail(response).as_deref(),
+        Some("22003!value too large\n42000!row 2 rejected")
+    );
+    assert_eq!(ReplyParser::error_detail(b"!oops").as_deref(), Some("oops"));
+}
+
+#[test]
+fn test_open_result_ids() {
+    let open = |prefix: &str| ReplyParser::open_result_ids(prefix.as_bytes());
+    assert_eq!(open(""), [] as [u64; 0]);
+    // all rows included, nothing to close
+    assert_eq!(open("&1 3 2 1 2\n% x # name\n[ 1\t]\n[ 2"), [] as [u64; 0]);
+    assert_eq!(open("&1 3 5 1 2\n% x # name\n[ 1\t]\n[ 2"), [3]);
+    // the second header has been cut off
+    assert_eq!(open("&1 3 5 1 2\n[ 1\t]\n[ 2\t]\n&2 1 -1\n&1 4 9 1 1"), [3]);
+    assert_eq!(open("&1 3 5 1 2\n[ 1\t]\n[ 2\t]\n&1 4 9 1 1\n"), [3, 4]);
+    // without the number of rows included we cannot tell
+    assert_eq!(open("&1 3 5 1\n[ 1\t]\n"), [] as [u64; 0]);
+}
+
+#[test]
+fn test_parse_data_header_shapes() {
+    let parse = |reply: &str| match ReplyParser::new(reply.as_bytes().to_vec()).unwrap() {
+        ReplyParser::Data(rs) => rs,
+        other => panic!("expected result set, got {other:?}"),
+    };
+
+    // with size headers: four fields, length and typesizes present
+    let rs = parse(concat!(
+        "&1 3 5 2 2\n",
+        "% sys.t,\tsys.t # table_name\n",
+        "% s,\td # name\n",
+        "% varchar,\tdecimal # type\n",
+        "% 12,\t7 # length\n",
+        "% 0 0,\t7 3 # typesizes\n",
+        "[ \"a\",\t1.500\t]\n",
+        "[ \"b\",\t2.500\t]\n",
+    ));
+    assert_eq!(rs.result_id, 3);
+    assert_eq!(rs.total_rows, 5);
+    assert_eq!(rs.received_rows, 2);
+    assert_eq!(rs.to_close, Some(3));
+    assert_eq!(rs.columns[0].name(), "sys.t.s");
+    assert_eq!(rs.columns[0].sql_type(), &MonetType::Varchar(12));
+    assert_eq!(rs.columns[1].sql_type(), &MonetType::Decimal(7, 3));
+
+    // without size headers: three fields, no length and typesizes
+    let rs = parse(concat!(
+        "&1 4 2 2\n",
+        "% sys.t,\tsys.t # table_name\n",
+        "% i,\tts # name\n",
+        "% int,\ttimestamp # type\n",
+        "[ 1,\t2024-01-01 00:00:00.000000\t]\n",
+        "[ 2,\t2024-01-02 00:00:00.000000\t]\n",
+    ));
+    assert_eq!(rs.result_id, 4);
+    assert_eq!(rs.total_rows, 2);
+    assert_eq!(rs.to_close, None);
+    assert_eq!(rs.columns[0].sql_type(), &MonetType::Int);
+    // without typesizes, temporal types get the server's precision
+    assert_eq!(rs.columns[1].sql_type(), &MonetType::Timestamp(6));
+
+    // without size headers, not all rows included
+    let rs = parse(concat!(
+        "&1 5 10 1\n",
+        "% sys.t # table_name\n",
+        "% i # name\n",
+        "% int # type\n",
+        "[ 1\t]\n",
+    ));
+    assert_eq!(rs.total_rows, 10);
+    assert_eq!(rs.received_rows, 1);
+    assert_eq!(rs.to_close, Some(5));
+
+    // two fields is still an error
+    assert!(ReplyParser::new(b"&1 5 10\n".to_vec()).is_err());
+}
+
+#[test]
+fn test_parse_data_inconsistent_counts() {
+    let reply = "&1 3 1 1 2\n% .x # table_name\n% x # name\n% int # type\n[ 1\t]\n[ 2\t]\n";
+    assert!(matches!(
+        ReplyParser::new(reply.as_bytes().to_vec()),
+        Err(BadReply::InvalidHeader(_))
+    ));
+    let reply = "&1 3 1 1\n% .x # table_name\n% x # name\n% int # type\n[ 1\t]\n[ 2\t]\n";
+    assert!(matches!(
+        ReplyParser::new(reply.as_bytes().to_vec()),
+        Err(BadReply::InvalidHeader(_))
+    ));
+}
@@ -15,8 +15,14 @@ pub struct RowSet {
     buf: ReplyBuf,
     ncols: usize,
     fields: Vec<Option<(*const u8, usize)>>,
+    quoted: Vec<bool>,
 }
 
+// SAFETY: the pointers in `fields` point into the heap allocation owned by
+// `buf`, which moves along with the RowSet. Nothing else refers to that
+// allocation so sending the RowSet to another thread is fine. It is not Sync.
+unsafe impl Send for RowSet {}
+
 // [ 1,→"one"→]↵
 // [ 42,→"forty-two"→]↵
 // [ -1,→"a\\\"b"→]↵
@@ -24,24 +30,47 @@ pub struct RowSet {
 impl RowSet {
     pub fn new(buf: ReplyBuf, ncols: usize) -> Self {
         let fields = vec![None; ncols];
-        RowSet { buf, ncols, fields }
+        RowSet {
+            buf,
+            ncols,
+            fields,
+            quoted: vec![],
+        }
+    }
+
+    /// The number of bytes of the reply this row set was parsed from.
+    pub fn buffer_len(&self) -> usize {
+        self.buf.total_len()
+    }
+
+    /// Mark which columns hold strings. In those columns only NULL may be
+    /// sent unquoted, any other unquoted value means we have lost track of
+    /// the protocol and is reported as [`BadReply::UnquotedString`].
+    pub fn set_quoted_columns(&mut self, quoted: Vec<bool>) {
+        self.quoted = quoted;
     }
 
     pub fn advance(&mut self) -> RResult<bool> {
-        let ret = self.do_advance();
+        self.advance_partial(self.ncols)
+    }
+
+    /// Like [`advance()`](`RowSet::advance`) but only locate the first `n`
+    /// fields and skip the rest of the row. The other fields read as NULL.
+    pub fn advance_partial(&mut self, n: usize) -> RResult<bool> {
+        let ret = self.do_advance(n.min(self.ncols));
         if ret.is_err() {
             self.fields.clear();
         }
         ret
     }
 
-    fn do_advance(&mut self) -> RResult<bool> {
+    fn do_advance(&mut self, n: usize) -> RResult<bool> {
         if !self.buf.peek().starts_with(b"[") {
             self.fields.fill(None);
             return Ok(false);
         }
         self.buf.consume(2);
-        for (i, field) in self.fields.iter_mut().enumerate() {
+        for (i, field) in self.fields.iter_mut().enumerate().take(n) {
             let comma_skip = (i + 1 < self.ncols) as usize;
             let Some(first) = self.buf.peek().first() else {
                 return Err(BadReply::UnexpectedEnd);
@@ -73,6 +102,8 @@ impl RowSet {
                     let adjusted = &rough[..rough.len() - comma_skip];
                     *field = if adjusted == b"NULL" {
                         None
+                    } else if self.quoted.get(i) == Some(&true) {
+                        return Err(BadReply::UnquotedString(i));
                     } else {
                         Some((adjusted.as_ptr(), adjusted.len()))
                     };
@@ -80,6 +111,16 @@ impl RowSet {
             }
         }
 
+        if n < self.ncols {
+            self.fields[n..].fill(None);
+            // newlines in string values are escaped so the row ends at the first one
+            let Some(pos) = self.buf.find(b'\n') else {
+                return Err(BadReply::UnexpectedEnd);
+            };
+            self.buf.consume(pos + 1);
+            return Ok(true);
+        }
+
         // now we should be looking at the trailing ]
         if !self.buf.peek().starts_with(b"]\n") {
             return Err(BadReply::SepNotFound(b']'));
@@ -88,6 +129,26 @@ impl RowSet {
         Ok(true)
     }
 
+    /// Move past the next row without locating its fields. Afterwards all
+    /// fields read as NULL.
+    pub fn skip(&mut self) -> RResult<bool> {
+        Ok(self.take_raw_row()?.is_some())
+    }
+
+    /// Like [`skip()`](`RowSet::skip`) but return the text of the row as sent
+    /// by the server, including the trailing newline.
+    pub fn take_raw_row(&mut self) -> RResult<Option<&[u8]>> {
+        self.fields.fill(None);
+        if !self.buf.peek().starts_with(b"[") {
+            return Ok(None);
+        }
+        // newlines in string values are escaped so the row ends at the first one
+        let Some(pos) = self.buf.find(b'\n') else {
+            return Err(BadReply::UnexpectedEnd);
+        };
+        Ok(Some(self.buf.consume(pos + 1)))
+    }
+
     pub fn finish(mut self) -> ReplyBuf {
         if let Some(idx) = self.buf.find_line(b'&') {
             self.buf.consume(idx);
@@ -270,3 +331,49 @@ fn test_finish() {
     let buf = rs.finish();
     assert_eq!(BStr::new(buf.peek()), BStr::new("&lalala\n"));
 }
+
+#[test]
+fn test_rowset_skip() {
+    let testdata = "[ \"a\\nb\",\t1\t]\n[ \"c\",\t2\t]\n[ \"d\",\t3\t]\n&2 1 -1\n";
+    let mut rs = RowSet::new(ReplyBuf::new(testdata.into()), 2);
+
+    assert!(rs.advance().unwrap());
+    assert_eq!(rs.get_str(1), Some("1"));
+    assert!(rs.skip().unwrap());
+    assert_eq!(rs.get_str(1), None);
+    assert!(rs.advance().unwrap());
+    assert_eq!(rs.get_str(0), Some("d"));
+    assert_eq!(rs.get_str(1), Some("3"));
+    assert!(!rs.skip().unwrap());
+    assert!(rs.finish().peek().starts_with(b"&2"));
+}
+
+#[test]
+fn test_rowset_advance_partial() {
+    let testdata = "[ 1,\t\"a\\tb\",\t\"x\"\t]\n[ 2,\t\"c\",\tNULL\t]\n&2 1 -1\n";
+    let mut rs = RowSet::new(ReplyBuf::new(testdata.into()), 3);
+
+    assert!(rs.advance_partial(2).unwrap());
+    assert_eq!(rs.get_str(0), Some("1"));
+    assert_eq!(rs.get_str(1), Some("a\tb"));
+    assert_eq!(rs.get_str(2), None);
+    assert!(rs.advance_partial(1).unwrap());
+    assert_eq!(rs.get_str(0), Some("2"));
+    assert_eq!(rs.get_str(1), None);
+    assert!(!rs.advance_partial(1).unwrap());
+    assert!(rs.finish().peek().starts_with(b"&2"));
+}
+
+#[test]
+fn test_rowset_quoted_columns() {
+    let testdata = "[ NULL,\t\"NULL\",\t1\t]\n[ \"a\",\toops,\t2\t]\n";
+    let mut rs = RowSet::new(ReplyBuf::new(testdata.into()), 3);
+    rs.set_quoted_columns(vec![true, true, false]);
+
+    assert!(rs.advance().unwrap());
+    assert_eq!(rs.get_str(0), None);
+    assert_eq!(rs.get_str(1), Some("NULL"));
+    assert_eq!(rs.get_str(2), Some("1"));
+
+    assert_eq!(rs.advance(), Err(BadReply::UnquotedString(1)));
+}
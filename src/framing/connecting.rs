@@ -26,14 +26,21 @@ use std::os::unix::net::UnixStream;
 use gethostname;
 
 use crate::{
-    cursor::delayed::{DelayedCommands, ExpectedResponse},
-    framing::{reading::MapiReader, writing::MapiBuf},
-    parms::{Parameters, ParmError, Validated},
-    util::{hash_algorithms, ioerror::IoError},
+    cursor::{
+        delayed::{DelayedCommands, ExpectedResponse},
+        CursorError,
+    },
+    framing::{
+        reading::{MapiReader, MessageTooLong},
+        tls,
+        writing::MapiBuf,
+    },
+    parms::{render_bool, Parameters, ParmError, Validated},
+    util::{errorsource::ErrorSource, hash_algorithms, ioerror::IoError},
     PUBLIC_NAME,
 };
 
-use super::{ServerSock, ServerState};
+use super::{reply_size_arg, Negotiated, ServerSock, ServerState};
 
 /// An error that occurs while trying to connect to MonetDB.
 #[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
@@ -51,7 +58,11 @@ pub enum ConnectError {
     #[error("TLS (monetdbs://) has not been enabled")]
     TlsNotSupported,
     #[error("TLS error: {0}")]
-    TlsError(String),
+    TlsError(#[source] ErrorSource),
+    #[error("server does not seem to use TLS on this port, try monetdb:// instead of monetdbs://")]
+    ServerNotTls,
+    #[error("server seems to expect TLS on this port, try monetdbs:// instead of monetdb://")]
+    ServerExpectsTls,
     #[error("only language=sql is supported")]
     OnlySqlSupported,
     #[error("too many redirects")]
@@ -62,16 +73,30 @@ pub enum ConnectError {
     UnexpectedResponse(String),
     #[error("Unix domain sockets are not supported on this platform")]
     UnixDomain,
+    /// Retrieving the server metadata failed, see
+    /// [`Parameters::set_fetch_metadata_on_connect()`].
+    #[error("could not retrieve server metadata: {0}")]
+    Metadata(#[source] CursorError),
 }
 
 pub type ConnectResult<T> = Result<T, ConnectError>;
 
+impl ConnectError {
+    /// Return `true` if trying again later might succeed. This is the case
+    /// for IO errors such as a refused connection or a timeout, but not for
+    /// example for invalid parameters or a rejected login.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ConnectError::IO(_))
+    }
+}
+
 impl From<io::Error> for ConnectError {
     fn from(value: io::Error) -> Self {
         IoError::from(value).into()
     }
 }
 
+/// Byte order, as announced by client and server during the handshake.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Endian {
     Big,
@@ -79,6 +104,7 @@ pub enum Endian {
 }
 
 impl Endian {
+    /// The byte order of the machine this code runs on.
     #[cfg(target_endian = "little")]
     pub const NATIVE: Endian = Endian::Lit;
 
@@ -97,13 +123,12 @@ impl fmt::Display for Endian {
 }
 
 #[cfg(not(unix))]
-fn connect_unix_socket(_parms: &Validated) -> ConnectResult<ServerSock> {
+fn connect_unix_socket(_path: &str) -> ConnectResult<ServerSock> {
     Err(ConnectError::UnixDomain)
 }
 
 #[cfg(unix)]
-fn connect_unix_socket(parms: &Validated) -> ConnectResult<ServerSock> {
-    let path = parms.connect_unix.as_ref();
+fn connect_unix_socket(path: &str) -> ConnectResult<ServerSock> {
     // UnixStream has no connect_timeout method, but unix domain sockets
     // are unlikely to hang anyway.
     match UnixStream::connect(path) {
@@ -112,6 +137,10 @@ fn connect_unix_socket(parms: &Validated) -> ConnectResult<ServerSock> {
             s.write_all(b"0")?;
             Ok(ServerSock::new(s))
         }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            debug!("no Unix Domain socket found at {path}");
+            Err(e.into())
+        }
         Err(e) => {
             debug!("{path}: {e}");
             Err(e.into())
@@ -160,10 +189,20 @@ fn connect_tcp_socket(parms: &Validated) -> io::Result<ServerSock> {
 fn connect_socket(parms: &Validated) -> ConnectResult<ServerSock> {
     let mut err: Option<ConnectError> = None;
 
-    if parms.connect_unix.is_empty() {
-        match connect_unix_socket(parms) {
-            Ok(s) => return Ok(s),
-            Err(e) => err = Some(e),
+    // Where Unix Domain sockets are not available, for example on Windows,
+    // only try one if there is no TCP alternative so the user gets to see
+    // ConnectError::UnixDomain.
+    let try_unix = !parms.connect_unix.is_empty() && (cfg!(unix) || parms.connect_tcp.is_empty());
+    if try_unix {
+        let fallback = parms.connect_unix_fallback.as_deref();
+        for path in [Some(parms.connect_unix.as_ref()), fallback]
+            .into_iter()
+            .flatten()
+        {
+            match connect_unix_socket(path) {
+                Ok(s) => return Ok(s),
+                Err(e) => err = Some(e),
+            }
         }
     }
     if !parms.connect_tcp.is_empty() {
@@ -207,58 +246,146 @@ type TlsImplementation = dyn Fn(&Validated, ServerSock) -> ConnectResult<ServerS
 enum Login {
     Redirect(String),
     Restart(ServerSock),
-    Complete(ServerSock, ServerState),
+    Complete(ServerSock, ServerState, Negotiated),
 }
 
 pub fn establish_connection(
     mut parms: Parameters,
-) -> ConnectResult<(ServerSock, ServerState, DelayedCommands)> {
-    'redirect: for _ in 0..10 {
+) -> ConnectResult<(ServerSock, ServerState, Negotiated, DelayedCommands)> {
+    // Both redirects to another server and proxy restarts count as a hop.
+    let mut restarted = None;
+    for _ in 0..10 {
         let validated = parms.validate()?;
-        if log_enabled!(log::Level::Debug) {
-            if let Ok(url) = parms.url_without_credentials() {
-                debug!("connecting to {url}");
-            }
-        }
-        let mut sock = connect_socket(&validated)?;
-        'restart: loop {
-            let (login, mut delayed) = login(&validated, sock)?;
-            match login {
-                Login::Complete(sock, state) => {
-                    // Send the delayed commands, do not wait to receive the
-                    // reply, we will do that later
-                    return match delayed.send_delayed(sock) {
-                        Ok(sock) => Ok((sock, state, delayed)),
-                        Err(e) => Err(ConnectError::Rejected(e.to_string())),
-                    };
-                }
-                Login::Redirect(url) => {
-                    debug!("redirected to {url}");
-                    parms.apply_url(&url)?;
-                    continue 'redirect;
+        let sock = match restarted.take() {
+            Some(sock) => sock,
+            None => {
+                if log_enabled!(log::Level::Debug) {
+                    if let Ok(url) = parms.url_without_credentials() {
+                        debug!("connecting to {url}");
+                    }
                 }
-                Login::Restart(s) => {
-                    debug!("local redirect, restarting authentication");
-                    sock = s;
-                    continue 'restart;
+                connect_socket(&validated)?
+            }
+        };
+        let (login, mut delayed) = login(&validated, sock)?;
+        match login {
+            Login::Complete(sock, state, negotiated) => {
+                if delayed.expects("check_auto_commit")
+                    || delayed.expects("schema")
+                    || delayed.expects("init_statement")
+                {
+                    // The user must be able to rely on the autocommit
+                    // mode, the schema and the init statements so wait for
+                    // the server to confirm them.
+                    let sock = verify_delayed(sock, &mut delayed, validated.autocommit)?;
+                    return Ok((sock, state, negotiated, delayed));
                 }
+                // Send the delayed commands, do not wait to receive the
+                // reply, we will do that later
+                return match delayed.send_delayed(sock) {
+                    Ok(sock) => Ok((sock, state, negotiated, delayed)),
+                    Err(e) => Err(ConnectError::Rejected(e.to_string())),
+                };
+            }
+            Login::Redirect(url) => {
+                debug!("redirected to {url}");
+                parms.apply_url(&url)?;
+            }
+            Login::Restart(sock) => {
+                // The proxy has connected us to the actual server, which
+                // will send a new challenge. The user's credentials are
+                // still in parms, only the proxy itself is answered with
+                // the 'merovingian' user, see challenge_response().
+                debug!("local redirect, restarting authentication");
+                restarted = Some(sock);
             }
         }
     }
     Err(ConnectError::TooManyRedirects)
 }
 
+/// Send the delayed commands and wait for the responses. Any error, for example
+/// because the schema does not exist, rejects the login. If the server reports
+/// the autocommit mode with `&4` in response to `Xauto_commit` or to the
+/// `ROLLBACK` added by [`plan_handshake()`], check that it matches the requested
+/// mode. Only `Xauto_commit` may be answered with an empty response, the
+/// `ROLLBACK` that follows it settles the mode.
+fn verify_delayed(
+    sock: ServerSock,
+    delayed: &mut DelayedCommands,
+    requested: bool,
+) -> ConnectResult<ServerSock> {
+    let rejected = |e: CursorError| ConnectError::Rejected(e.to_string());
+    let mut buffer = vec![];
+    let sock = delayed.send_delayed(sock).map_err(rejected)?;
+    let (sock, result) = delayed
+        .recv_delayed_with(sock, &mut buffer, |description, response| {
+            let command = match description {
+                "auto_commit" => "Xauto_commit",
+                "check_auto_commit" => "ROLLBACK",
+                _ => return Ok(()),
+            };
+            let confirmed = match response {
+                [b'&', b'4', b' ', b't', ..] => true,
+                [b'&', b'4', b' ', b'f', ..] => false,
+                [] if command == "Xauto_commit" => {
+                    debug!("server did not report the autocommit mode after Xauto_commit");
+                    return Ok(());
+                }
+                _ => {
+                    let response = String::from_utf8_lossy(response);
+                    return Err(CursorError::Server(format!(
+                        "unexpected response to {command}: {response:?}"
+                    )));
+                }
+            };
+            if confirmed == requested {
+                Ok(())
+            } else {
+                let requested = render_bool(requested);
+                let confirmed = render_bool(confirmed);
+                Err(CursorError::Server(format!(
+                    "requested autocommit={requested} but server reports autocommit={confirmed}"
+                )))
+            }
+        })
+        .map_err(rejected)?;
+    result.map_err(rejected)?;
+    Ok(sock)
+}
+
 fn login(parms: &Validated, sock: ServerSock) -> ConnectResult<(Login, DelayedCommands)> {
     let mut server_message = String::with_capacity(1000);
     let mut mbuf = MapiBuf::new();
 
-    // read the challenge
-    let sock = MapiReader::to_limited_string(sock, &mut server_message, 5000)?;
+    // read the challenge, keeping an eye out for signs that the server
+    // expects TLS when we don't or vice versa
+    let mut recorder = HeadRecorder::new(sock);
+    let read = MapiReader::to_limited_string(&mut recorder, &mut server_message, 5000).map(|_| ());
+    let mismatch = if parms.tls {
+        read.as_ref().is_err_and(tls::is_plaintext_error)
+    } else {
+        looks_like_tls(recorder.head())
+    };
+    if let Err(e) = read {
+        return Err(if mismatch {
+            tls_mismatch(parms)
+        } else if MessageTooLong::is_cause_of(&e) {
+            ConnectError::InvalidChallenge("challenge exceeds 5000 bytes".into())
+        } else {
+            e.into()
+        });
+    }
+    let sock = recorder.into_inner();
 
     // determine the response
-    let chal = Challenge::new(&server_message)?;
+    let chal = match Challenge::new(&server_message) {
+        Ok(chal) => chal,
+        Err(_) if mismatch => return Err(tls_mismatch(parms)),
+        Err(e) => return Err(e),
+    };
     let mut response = String::with_capacity(500);
-    let (state, delayed) = challenge_response(parms, &chal, &mut response)?;
+    let (state, negotiated, delayed) = challenge_response(parms, &chal, &mut response)?;
 
     // send the response
     mbuf.append(response);
@@ -266,10 +393,16 @@ fn login(parms: &Validated, sock: ServerSock) -> ConnectResult<(Login, DelayedCo
 
     // read the server response
     server_message.clear();
-    let sock = MapiReader::to_limited_string(sock, &mut server_message, 5000)?;
+    let sock = MapiReader::to_limited_string(sock, &mut server_message, 5000).map_err(|e| {
+        if MessageTooLong::is_cause_of(&e) {
+            ConnectError::UnexpectedResponse("response exceeds 5000 bytes".into())
+        } else {
+            e.into()
+        }
+    })?;
 
     // process the server
-    let login = process_redirects(sock, state, &server_message)?;
+    let login = process_redirects(sock, state, negotiated, &server_message)?;
     Ok((login, delayed))
 }
 
@@ -277,10 +410,10 @@ fn challenge_response(
     parms: &Validated,
     chal: &Challenge,
     response: &mut String,
-) -> ConnectResult<(ServerState, DelayedCommands)> {
+) -> ConnectResult<(ServerState, Negotiated, DelayedCommands)> {
     use fmt::Write;
 
-    let my_endian = Endian::NATIVE;
+    let my_endian = parms.client_endian;
     let (user, password) = if chal.server_type == "merovingian" {
         ("merovingian", "")
     } else {
@@ -304,7 +437,8 @@ fn challenge_response(
     };
 
     let response_algos = chal.response_algos;
-    let Some((algo_name, algo)) = hash_algorithms::find_algo(response_algos) else {
+    let Some((algo_name, algo)) = hash_algorithms::find_preferred_algo(&parms.hash, response_algos)
+    else {
         return Err(ConnectError::UnsupportedHashAlgo(
             response_algos.to_string(),
         ));
@@ -325,80 +459,29 @@ fn challenge_response(
     )
     .unwrap();
 
-    let mut state = ServerState::new(prehash_algo_name);
-    let mut delayed = DelayedCommands::new();
-
-    if parms.language == "sql" {
-        // Append handshake options to the response, numbers based on enum
-        // mapi_handshake_options_levels in mapi.h
-
-        let level_limit = chal.sql_handshake_option_level;
-        let mut sep = "";
-
-        let mut arrange = |lvl: u8, key: &'static str, value: i64, cmd: fmt::Arguments| {
-            if lvl < level_limit {
-                // use a handshake option
-                write!(response, "{sep}{key}={value}").unwrap();
-                sep = ",";
-            } else {
-                // use a delayed Xcommand
-                delayed.add(key, cmd)
-            }
-        };
-
-        // MAPI_HANDSHAKE_AUTOCOMMIT = 1,
-        if state.initial_auto_commit != parms.autocommit {
-            let v = parms.autocommit as i64;
-            arrange(1, "auto_commit", v, format_args!("Xauto_commit {v}"));
-            state.initial_auto_commit = parms.autocommit;
-        }
-
-        // MAPI_HANDSHAKE_REPLY_SIZE = 2,
-        if state.reply_size != parms.replysize {
-            let v = parms.replysize;
-            arrange(2, "reply_size", v as i64, format_args!("Xreply_size {v}"));
-            state.reply_size = parms.replysize;
-        }
-
-        // MAPI_HANDSHAKE_SIZE_HEADER = 3,
-        // always enabled. note: Xcommand has no underscore
-        arrange(3, "size_header", 1, format_args!("Xsizeheader 1"));
-
-        // MAPI_HANDSHAKE_COLUMNAR_PROTOCOL = 4,
-        // (do not enable that)
-
-        // MAPI_HANDSHAKE_TIME_ZONE = 5,
-        let seconds_east = if let Some(tz_seconds) = parms.connect_timezone_seconds {
-            tz_seconds
-        } else {
-            // If a date/time crate has been activated, use that.
-            // Otherwise, return UTC.
-            let implementations = [
-                #[cfg(feature = "time")]
-                crate::convert::temporal_time::timezone_offset_east_of_utc,
-                // Fallback
-                || 0i32,
-            ];
-            (implementations[0])()
-        };
-        if state.time_zone_seconds != seconds_east {
-            let mins = seconds_east / 60;
-            let sign = if mins < 0 { '-' } else { '+' };
-            let a = mins.abs();
-            let h = a / 60;
-            let m = a % 60;
-            arrange(
-                5,
-                "time_zone",
-                seconds_east as i64,
-                format_args!("sSET TIME ZONE INTERVAL '{sign}{h:02}:{m:02}' HOUR TO MINUTE;"),
-            );
-            state.time_zone_seconds = seconds_east;
-        }
-    }
-
+    let negotiated = Negotiated {
+        protocol: chal.protocol,
+        hash_algo: algo_name,
+        prehash_algo: prehash_algo_name,
+        binary_level: chal.binary,
+    };
+    let mut state = ServerState::new();
+    state.slow_query_log = parms.slow_query_log;
+    state.max_prefetch = parms.maxprefetch;
+    state.max_result_bytes = parms.max_result_bytes;
+    state.fetch_metadata = parms.fetch_metadata_on_connect;
+    state.server_endian = chal.endian;
+
+    let (options, mut delayed) = if parms.language == "sql" {
+        plan_handshake(&mut state, parms, chal.sql_handshake_option_level)
+    } else {
+        (String::new(), DelayedCommands::new())
+    };
+    response.push_str(&options);
     response.push(':'); // after the handshake options
 
+    // Only construct ClientInfo when it will be sent, its Default impl looks
+    // up the host name and process id.
     if chal.clientinfo && parms.client_info {
         if parms.language == "sql" {
             let mut info = ClientInfo::default();
@@ -408,6 +491,9 @@ fn challenge_response(
             if !parms.client_remark.is_empty() {
                 info.client_remark = Cow::Owned(parms.client_remark.to_string());
             }
+            if !parms.client_library.is_empty() {
+                info.client_library = Cow::Owned(parms.client_library.to_string());
+            }
             write!(delayed.buffer, "{}", SqlForm(&info)).unwrap();
             delayed.buffer.end();
             delayed.responses.push(ExpectedResponse {
@@ -418,10 +504,339 @@ fn challenge_response(
         }
     }
 
-    Ok((state, delayed))
+    Ok((state, negotiated, delayed))
 }
 
-fn process_redirects(sock: ServerSock, state: ServerState, reply: &str) -> ConnectResult<Login> {
+/// Decide how to apply the SQL session settings: as handshake options
+/// appended to the challenge response if the server supports them at
+/// `level_limit`, otherwise as delayed commands. Returns the handshake
+/// options and the delayed commands. Updates `state` to reflect the settings.
+/// Options listed in `suppress_handshake_options` always become delayed
+/// commands.
+///
+/// The levels are based on enum mapi_handshake_options_levels in mapi.h.
+fn plan_handshake(
+    state: &mut ServerState,
+    parms: &Validated,
+    level_limit: u8,
+) -> (String, DelayedCommands) {
+    use fmt::Write;
+
+    let mut options = String::new();
+    let mut delayed = DelayedCommands::new();
+    let mut sep = "";
+
+    let mut arrange = |lvl: u8, key: &'static str, value: i64, cmd: fmt::Arguments| {
+        if lvl < level_limit && !parms.suppress_handshake_options.contains(&key) {
+            // use a handshake option
+            write!(options, "{sep}{key}={value}").unwrap();
+            sep = ",";
+        } else {
+            // use a delayed Xcommand
+            delayed.add(key, cmd)
+        }
+    };
+
+    // MAPI_HANDSHAKE_AUTOCOMMIT = 1,
+    if state.initial_auto_commit != parms.autocommit {
+        let v = parms.autocommit as i64;
+        arrange(1, "auto_commit", v, format_args!("Xauto_commit {v}"));
+        state.initial_auto_commit = parms.autocommit;
+    }
+
+    // MAPI_HANDSHAKE_REPLY_SIZE = 2,
+    if state.reply_size != parms.replysize {
+        let v = reply_size_arg(parms.replysize);
+        arrange(2, "reply_size", v, format_args!("Xreply_size {v}"));
+        state.reply_size = parms.replysize;
+    }
+
+    // MAPI_HANDSHAKE_SIZE_HEADER = 3,
+    // always enabled. note: Xcommand has no underscore
+    arrange(3, "size_header", 1, format_args!("Xsizeheader 1"));
+
+    // MAPI_HANDSHAKE_COLUMNAR_PROTOCOL = 4,
+    // (do not enable that)
+
+    // MAPI_HANDSHAKE_TIME_ZONE = 5,
+    let seconds_east = parms
+        .connect_timezone_seconds
+        .unwrap_or_else(crate::convert::local_timezone_seconds);
+    if state.time_zone_seconds != seconds_east {
+        let mins = seconds_east / 60;
+        let sign = if mins < 0 { '-' } else { '+' };
+        let a = mins.abs();
+        let h = a / 60;
+        let m = a % 60;
+        arrange(
+            5,
+            "time_zone",
+            seconds_east as i64,
+            format_args!("sSET TIME ZONE INTERVAL '{sign}{h:02}:{m:02}' HOUR TO MINUTE;"),
+        );
+        state.time_zone_seconds = seconds_east;
+    }
+
+    // Neither the handshake option nor Xauto_commit reliably reports the
+    // resulting autocommit mode. ROLLBACK does: it replies &4 f in manual
+    // commit mode and fails in autocommit mode. The settings above are not
+    // transactional, so there is nothing to roll back yet.
+    if !parms.autocommit {
+        delayed.add("check_auto_commit", "sROLLBACK;");
+    }
+
+    // There is no handshake option for the query timeout, and the server
+    // counts in whole seconds.
+    if let Some(timeout) = parms.query_timeout {
+        let secs = timeout.as_millis().div_ceil(1000);
+        delayed.add(
+            "query_timeout",
+            format_args!("sCALL sys.setquerytimeout({secs});"),
+        );
+    }
+
+    // There is no handshake option for the schema.
+    if !parms.schema.is_empty() {
+        let schema = SqlIdent(&parms.schema);
+        delayed.add("schema", format_args!("sSET SCHEMA {schema};"));
+    }
+
+    for sql in parms.init_statements {
+        delayed.add("init_statement", format_args!("s{sql}\n;"));
+    }
+
+    (options, delayed)
+}
+
+/// Split the buffered delayed commands into separate messages.
+#[cfg(test)]
+fn decode_delayed(delayed: &mut DelayedCommands) -> Vec<String> {
+    let mut raw = delayed.buffer.reset();
+    let mut commands = vec![];
+    while !raw.is_empty() {
+        let mut msg = vec![];
+        raw = MapiReader::to_end(raw, &mut msg).unwrap();
+        commands.push(String::from_utf8(msg).unwrap());
+    }
+    commands
+}
+
+#[test]
+fn test_plan_handshake() {
+    let parms = Parameters::default()
+        .with_autocommit(false)
+        .unwrap()
+        .with_replysize(50)
+        .unwrap()
+        .with_timezone(90)
+        .unwrap()
+        .with_schema("my schema")
+        .unwrap()
+        .with_init_statement("SET ROLE analyst");
+    let validated = parms.validate().unwrap();
+
+    let plan = |level: u8| {
+        let mut state = ServerState::new();
+        let (options, mut delayed) = plan_handshake(&mut state, &validated, level);
+        assert!(!state.initial_auto_commit);
+        assert_eq!(state.reply_size, 50);
+        assert_eq!(state.time_zone_seconds, 5400);
+
+        let commands = decode_delayed(&mut delayed);
+        assert_eq!(commands.len(), delayed.responses.len());
+        (options, commands)
+    };
+
+    let settings = [
+        ("auto_commit=0", "Xauto_commit 0\n"),
+        ("reply_size=50", "Xreply_size 50\n"),
+        ("size_header=1", "Xsizeheader 1\n"),
+        (
+            "time_zone=5400",
+            "sSET TIME ZONE INTERVAL '+01:30' HOUR TO MINUTE;\n",
+        ),
+    ];
+    // level 4 is the columnar protocol, which we never enable
+    let inline_counts = [0, 0, 1, 2, 3, 3, 4];
+    for (level, n) in inline_counts.into_iter().enumerate() {
+        let options: Vec<_> = settings[..n].iter().map(|(opt, _)| *opt).collect();
+        let mut commands: Vec<_> = settings[n..].iter().map(|(_, cmd)| *cmd).collect();
+        commands.push("sROLLBACK;\n");
+        commands.push("sSET SCHEMA \"my schema\";\n");
+        commands.push("sSET ROLE analyst\n;\n");
+        assert_eq!(
+            plan(level as u8),
+            (
+                options.join(","),
+                commands.iter().map(|c| c.to_string()).collect()
+            ),
+            "level {level}"
+        );
+    }
+}
+
+#[test]
+fn test_plan_suppressed_options() {
+    let parms = Parameters::default()
+        .with_replysize(50)
+        .unwrap()
+        .with_timezone(90)
+        .unwrap()
+        .with_suppress_handshake_options(" time_zone, replysize")
+        .unwrap();
+    let validated = parms.validate().unwrap();
+    assert_eq!(
+        validated.suppress_handshake_options,
+        ["time_zone", "reply_size"]
+    );
+
+    let mut state = ServerState::new();
+    let (options, mut delayed) = plan_handshake(&mut state, &validated, 9);
+    assert_eq!(options, "size_header=1");
+    assert_eq!(state.reply_size, 50);
+    assert_eq!(state.time_zone_seconds, 5400);
+
+    let commands = decode_delayed(&mut delayed);
+    assert_eq!(
+        commands,
+        [
+            "Xreply_size 50\n",
+            "sSET TIME ZONE INTERVAL '+01:30' HOUR TO MINUTE;\n"
+        ]
+    );
+
+    let parms = Parameters::default()
+        .with_suppress_handshake_options("time_zone,frobnicate")
+        .unwrap();
+    assert_eq!(
+        parms.validate(),
+        Err(ParmError::InvalidValue(
+            crate::parms::Parm::SuppressHandshakeOptions
+        ))
+    );
+}
+
+#[test]
+fn test_plan_unlimited_reply_size() {
+    for replysize in [0, -1, -100] {
+        let parms = Parameters::default().with_replysize(replysize).unwrap();
+        let validated = parms.validate().unwrap();
+        assert_eq!(validated.replysize, 0);
+
+        let mut state = ServerState::new();
+        let (options, _) = plan_handshake(&mut state, &validated, 9);
+        assert_eq!(state.reply_size, 0);
+        assert!(options.contains("reply_size=-1"), "{options}");
+
+        let mut state = ServerState::new();
+        let (_, mut delayed) = plan_handshake(&mut state, &validated, 0);
+        let commands = decode_delayed(&mut delayed);
+        assert_eq!(commands[0], "Xreply_size -1\n");
+    }
+}
+
+fn tls_mismatch(parms: &Validated) -> ConnectError {
+    if parms.tls {
+        ConnectError::ServerNotTls
+    } else {
+        ConnectError::ServerExpectsTls
+    }
+}
+
+/// Returns `true` if the data starts like a TLS record, for example the alert
+/// a TLS server sends when it receives our NUL bytes instead of a ClientHello.
+/// A MAPI block header could in theory start like this, so only use it to
+/// explain a failure to read or parse the challenge.
+fn looks_like_tls(head: &[u8]) -> bool {
+    matches!(head, [0x14..=0x17, 0x03, ..])
+}
+
+#[test]
+#[cfg(not(feature = "rustls"))]
+fn test_without_tls_backend() {
+    use std::{io::Read, net::TcpListener};
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let mut received = vec![];
+        for _ in 0..2 {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut buf = vec![];
+            conn.read_to_end(&mut buf).unwrap();
+            received.push(buf);
+        }
+        received
+    });
+
+    // monetdbs:// fails at runtime
+    let parms = Parameters::from_url(&format!("monetdbs://127.0.0.1:{port}/demo")).unwrap();
+    let err = connect_socket(&parms.validate().unwrap()).unwrap_err();
+    assert_eq!(err, ConnectError::TlsNotSupported);
+
+    // the plaintext path works
+    let parms = Parameters::from_url(&format!("monetdb://127.0.0.1:{port}/demo")).unwrap();
+    let sock = connect_socket(&parms.validate().unwrap()).unwrap();
+    drop(sock);
+
+    let received = server.join().unwrap();
+    assert_eq!(received[0], b"");
+    assert_eq!(received[1], [0u8; 8]);
+}
+
+#[test]
+fn test_looks_like_tls() {
+    // TLS 1.2 alert: handshake failure
+    assert!(looks_like_tls(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28]));
+    // TLS handshake record
+    assert!(looks_like_tls(&[0x16, 0x03, 0x01]));
+    // header of a short final MAPI block
+    assert!(!looks_like_tls(&[0xA1, 0x00]));
+    assert!(!looks_like_tls(&[0x15]));
+    assert!(!looks_like_tls(&[]));
+}
+
+/// Reader that remembers the first few bytes that passed through it.
+struct HeadRecorder<R> {
+    inner: R,
+    head: [u8; 8],
+    head_len: usize,
+}
+
+impl<R> HeadRecorder<R> {
+    fn new(inner: R) -> Self {
+        HeadRecorder {
+            inner,
+            head: [0; 8],
+            head_len: 0,
+        }
+    }
+
+    fn head(&self) -> &[u8] {
+        &self.head[..self.head_len]
+    }
+
+    fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> io::Read for HeadRecorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let m = n.min(self.head.len() - self.head_len);
+        self.head[self.head_len..self.head_len + m].copy_from_slice(&buf[..m]);
+        self.head_len += m;
+        Ok(n)
+    }
+}
+
+fn process_redirects(
+    sock: ServerSock,
+    mut state: ServerState,
+    negotiated: Negotiated,
+    reply: &str,
+) -> ConnectResult<Login> {
     let reply = reply.trim_ascii();
 
     if reply.is_empty() || reply.starts_with("=OK") {
@@ -438,13 +853,19 @@ fn process_redirects(sock: ServerSock, state: ServerState, reply: &str) -> Conne
     } else if let Some(message) = reply.strip_prefix('!') {
         debug!("login rejected: {message}");
         return Err(ConnectError::Rejected(message.to_string()));
-    } else if let Some(message) = reply.strip_prefix('#') {
+    } else if reply.starts_with('#') {
+        let message = reply
+            .lines()
+            .map(|line| line.trim_start_matches('#').trim())
+            .collect::<Vec<_>>()
+            .join("\n");
         debug!("login complete with welcome message {message:?}");
+        state.welcome_message = Some(message);
     } else {
         debug!("unexpected response: {reply:?}");
         return Err(ConnectError::UnexpectedResponse(reply.to_string()));
     }
-    Ok(Login::Complete(sock, state))
+    Ok(Login::Complete(sock, state, negotiated))
 }
 
 #[derive(Debug)]
@@ -614,6 +1035,70 @@ impl ClientInfo {
     }
 }
 
+/// Renders a string as a double-quoted SQL identifier.
+struct SqlIdent<'a>(&'a str);
+
+impl fmt::Display for SqlIdent<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("\"")?;
+        for (i, part) in self.0.split('"').enumerate() {
+            if i > 0 {
+                f.write_str("\"\"")?;
+            }
+            f.write_str(part)?;
+        }
+        f.write_str("\"")
+    }
+}
+
+#[test]
+fn test_sql_ident() {
+    assert_eq!(SqlIdent("sys").to_string(), r#""sys""#);
+    assert_eq!(SqlIdent("my schema").to_string(), r#""my schema""#);
+    assert_eq!(SqlIdent(r#"a"b"#).to_string(), r#""a""b""#);
+}
+
+#[test]
+fn test_client_info_off() {
+    let chal = Challenge::new(
+        "abcdefgh:mserver:9:RIPEMD160,SHA512:LIT:SHA512:sql=9:BINARY=1:OOBINTR=1:CLIENTINFO:",
+    )
+    .unwrap();
+    let delayed_commands = |client_info: &str| {
+        let parms = crate::Parameters::default()
+            .with_client_info(client_info)
+            .unwrap();
+        let validated = parms.validate().unwrap();
+        let mut response = String::new();
+        let (_, _, mut delayed) = challenge_response(&validated, &chal, &mut response).unwrap();
+        String::from_utf8_lossy(delayed.buffer.reset()).into_owned()
+    };
+
+    assert!(delayed_commands("on").contains("Xclientinfo ClientHostname="));
+    assert!(!delayed_commands("off").contains("Xclientinfo"));
+}
+
+#[test]
+fn test_password_with_colon() {
+    let chal = Challenge::new("abcdefgh:mserver:9:SHA512:LIT:SHA512:").unwrap();
+    let parms = crate::Parameters::default()
+        .with_user("me")
+        .unwrap()
+        .with_password("se:cret")
+        .unwrap()
+        .with_database("demo")
+        .unwrap();
+    let validated = parms.validate().unwrap();
+    let mut response = String::new();
+    challenge_response(&validated, &chal, &mut response).unwrap();
+
+    let fields: Vec<_> = response.split(':').collect();
+    assert_eq!(fields[..2], ["LIT", "me"]);
+    let hashed = fields[2].strip_prefix("{SHA512}").unwrap();
+    assert!(hashed.bytes().all(|b| b.is_ascii_hexdigit()), "{response}");
+    assert_eq!(fields[3..6], ["sql", "demo", "FILETRANS"]);
+}
+
 struct SqlForm<'a>(&'a ClientInfo);
 
 impl fmt::Display for SqlForm<'_> {
@@ -12,13 +12,23 @@ pub mod reading;
 pub mod tls;
 pub mod writing;
 
-use std::{error, fmt, io, net::TcpStream, sync::Arc};
+use std::{
+    error, fmt, io,
+    net::TcpStream,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
 
 use crate::conn::InnerServerMetadata;
 
+use connecting::Endian;
+
 pub const BLOCKSIZE: usize = 8190;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -61,20 +71,57 @@ impl error::Error for FramingError {}
 #[derive(Debug, Clone)]
 pub struct ServerState {
     pub initial_auto_commit: bool,
+    /// 0 means the server sends all rows at once.
     pub reply_size: usize,
     pub time_zone_seconds: i32,
     pub sql_metadata: Option<Arc<InnerServerMetadata>>,
+    pub session_id: Option<i64>,
+    pub slow_query_log: Option<Duration>,
+    pub max_prefetch: Option<usize>,
+    pub max_result_bytes: Option<usize>,
+    pub fetch_metadata: bool,
+    pub server_endian: Endian,
+    /// The `#` lines sent by the server after a successful login, without
+    /// the `#`.
+    pub welcome_message: Option<String>,
+}
+
+/// The settings agreed on during the login. Unlike [`ServerState`] these do
+/// not change afterwards.
+#[derive(Debug, Clone, Copy)]
+pub struct Negotiated {
+    pub protocol: u8,
+    /// The algorithm used to hash the password in the login response.
+    pub hash_algo: &'static str,
+    /// The algorithm the server uses to store passwords.
     pub prehash_algo: &'static str,
+    pub binary_level: u16,
+}
+
+/// The argument of `Xreply_size` for the given reply size. The server uses
+/// -1 to mean no limit, we use 0.
+pub fn reply_size_arg(reply_size: usize) -> i64 {
+    if reply_size == 0 {
+        -1
+    } else {
+        reply_size as i64
+    }
 }
 
 impl ServerState {
-    fn new(prehash_algo: &'static str) -> Self {
+    fn new() -> Self {
         Self {
             initial_auto_commit: true,
             reply_size: 100,
             time_zone_seconds: 0,
             sql_metadata: None,
-            prehash_algo,
+            session_id: None,
+            slow_query_log: None,
+            max_prefetch: None,
+            max_result_bytes: None,
+            fetch_metadata: false,
+            server_endian: Endian::NATIVE,
+            welcome_message: None,
         }
     }
 }
@@ -87,34 +134,58 @@ impl ServerSockTrait for UnixStream {}
 impl ServerSockTrait for TcpStream {}
 
 #[derive(Debug)]
-pub struct ServerSock(Box<dyn ServerSockTrait>);
+pub struct ServerSock {
+    inner: Box<dyn ServerSockTrait>,
+    traffic: Arc<Traffic>,
+}
+
+/// The number of bytes read from and written to a [`ServerSock`].
+#[derive(Debug, Default)]
+pub struct Traffic {
+    pub sent: AtomicU64,
+    pub received: AtomicU64,
+}
 
 impl ServerSock {
     fn new(sock: impl ServerSockTrait) -> Self {
-        ServerSock(Box::new(sock))
+        ServerSock {
+            inner: Box::new(sock),
+            traffic: Arc::default(),
+        }
+    }
+
+    pub fn traffic(&self) -> Arc<Traffic> {
+        Arc::clone(&self.traffic)
+    }
+
+    fn count(counter: &AtomicU64, result: io::Result<usize>) -> io::Result<usize> {
+        if let Ok(n) = result {
+            counter.fetch_add(n as u64, Ordering::Relaxed);
+        }
+        result
     }
 }
 
 impl io::Read for ServerSock {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.read(buf)
+        Self::count(&self.traffic.received, self.inner.read(buf))
     }
 
     fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
-        self.0.read_vectored(bufs)
+        Self::count(&self.traffic.received, self.inner.read_vectored(bufs))
     }
 }
 
 impl io::Write for ServerSock {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.0.write(buf)
+        Self::count(&self.traffic.sent, self.inner.write(buf))
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.0.flush()
+        self.inner.flush()
     }
 
     fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
-        self.0.write_vectored(bufs)
+        Self::count(&self.traffic.sent, self.inner.write_vectored(bufs))
     }
 }
@@ -10,6 +10,20 @@ use std::io::{self, ErrorKind, Read};
 
 use super::{blockstate::BlockState, BLOCKSIZE};
 
+/// Wrapped in the [`io::Error`] returned by [`MapiReader::to_limited`] and
+/// [`MapiReader::to_limited_string`] if the message exceeds the limit.
+/// Can be detected using [`MessageTooLong::is_cause_of`].
+#[derive(Debug, thiserror::Error)]
+#[error("incoming message too long")]
+pub struct MessageTooLong;
+
+impl MessageTooLong {
+    pub fn is_cause_of(err: &io::Error) -> bool {
+        err.get_ref()
+            .is_some_and(|inner| inner.downcast_ref::<MessageTooLong>().is_some())
+    }
+}
+
 pub struct MapiReader<R> {
     inner: R,
     state: BlockState,
@@ -137,6 +151,35 @@ impl<R: Read> MapiReader<R> {
         reader.finish()
     }
 
+    /// Like [`to_end()`](`MapiReader::to_end`) but append at most `limit`
+    /// bytes to `buffer`. If the message is longer, the rest is read and
+    /// discarded so the connection stays in sync, and the total size of the
+    /// message is returned.
+    pub fn to_end_capped(
+        rd: R,
+        buffer: &mut Vec<u8>,
+        limit: usize,
+    ) -> io::Result<(R, Option<usize>)> {
+        let mut reader = Self::new(rd);
+        let n = (&mut reader).take(limit as u64).read_to_end(buffer)?;
+        if let BlockState::End = reader.state {
+            return Ok((reader.finish()?, None));
+        }
+        let mut total = n;
+        let mut scratch = [0u8; BLOCKSIZE];
+        loop {
+            match reader.read(&mut scratch)? {
+                0 => break,
+                nread => total += nread,
+            }
+        }
+        if total == limit {
+            Ok((reader.finish()?, None))
+        } else {
+            Ok((reader.finish()?, Some(total)))
+        }
+    }
+
     #[allow(dead_code)]
     pub fn to_limited(rd: R, buffer: &mut Vec<u8>, limit: usize) -> io::Result<R> {
         let mut reader = Self::new(rd);
@@ -144,10 +187,7 @@ impl<R: Read> MapiReader<R> {
         if let BlockState::End = reader.state {
             reader.finish()
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "incoming message too long",
-            ))
+            Err(io::Error::new(io::ErrorKind::InvalidData, MessageTooLong))
         }
     }
 
@@ -157,21 +197,18 @@ impl<R: Read> MapiReader<R> {
         if let BlockState::End = reader.state {
             reader.finish()
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "incoming message too long",
-            ))
+            Err(io::Error::new(io::ErrorKind::InvalidData, MessageTooLong))
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::{Cursor, Read};
+    use std::io::{self, Cursor, Read};
 
     use crate::{framing::blockstate::Header, util::referencedata::ReferenceData};
 
-    use super::MapiReader;
+    use super::{MapiReader, MessageTooLong};
 
     #[test]
     fn test_read() {
@@ -225,4 +262,53 @@ mod tests {
         rd.read_to_string(&mut message).unwrap();
         assert_eq!(message, "monetdb");
     }
+
+    #[test]
+    fn test_limited_string() {
+        let mut refd = ReferenceData::new();
+        let content = b"monetdb";
+        refd.data(Header::new(content.len(), true));
+        refd.data(content.as_slice());
+        let data = Vec::from(refd.as_slice());
+
+        let mut message = String::new();
+        MapiReader::to_limited_string(Cursor::new(data.clone()), &mut message, 10).unwrap();
+        assert_eq!(message, "monetdb");
+
+        let mut message = String::new();
+        let err = MapiReader::to_limited_string(Cursor::new(data), &mut message, 5).unwrap_err();
+        assert!(MessageTooLong::is_cause_of(&err));
+        assert!(!MessageTooLong::is_cause_of(
+            &io::ErrorKind::InvalidData.into()
+        ));
+    }
+
+    #[test]
+    fn test_capped() {
+        // a message in two blocks followed by another message
+        let mut refd = ReferenceData::new();
+        refd.data(Header::new(5, false));
+        refd.data(b"monet".as_slice());
+        refd.data(Header::new(2, true));
+        refd.data(b"db".as_slice());
+        refd.data(Header::new(4, true));
+        refd.data(b"next".as_slice());
+        let data = Vec::from(refd.as_slice());
+
+        let capped = |limit| {
+            let mut buffer = vec![];
+            let (rest, total) =
+                MapiReader::to_end_capped(Cursor::new(data.clone()), &mut buffer, limit).unwrap();
+            let mut next = vec![];
+            MapiReader::to_end(rest, &mut next).unwrap();
+            assert_eq!(next, b"next");
+            (String::from_utf8(buffer).unwrap(), total)
+        };
+
+        assert_eq!(capped(10), ("monetdb".to_string(), None));
+        assert_eq!(capped(7), ("monetdb".to_string(), None));
+        assert_eq!(capped(6), ("monetd".to_string(), Some(7)));
+        assert_eq!(capped(3), ("mon".to_string(), Some(7)));
+        assert_eq!(capped(0), ("".to_string(), Some(7)));
+    }
 }
@@ -8,3 +8,16 @@
 
 #[cfg(feature = "rustls")]
 pub mod rustls;
+
+use std::io;
+
+/// Returns `true` if the error returned while reading from a TLS connection
+/// suggests that the server is not speaking TLS at all.
+#[allow(unused_variables)]
+pub fn is_plaintext_error(err: &io::Error) -> bool {
+    #[cfg(feature = "rustls")]
+    if rustls::is_plaintext_error(err) {
+        return true;
+    }
+    false
+}
@@ -16,23 +16,36 @@ use crate::{
         ServerSock, ServerSockTrait,
     },
     parms::Validated,
+    util::errorsource::ErrorSource,
 };
 
 pub fn wrap_with_rustls(parms: &Validated, sock: ServerSock) -> ConnectResult<ServerSock> {
-    wrap_inner(parms, sock).map_err(|e| ConnectError::TlsError(e.to_string()))
+    wrap_inner(parms, sock).map_err(ConnectError::TlsError)
 }
 
-fn wrap_inner(
-    parms: &Validated,
-    sock: ServerSock,
-) -> Result<ServerSock, Box<dyn std::error::Error>> {
+/// Rustls reports an invalid content type when the first byte of the data
+/// sent by the server is not a valid TLS record type. A MAPI block header
+/// never is.
+pub fn is_plaintext_error(err: &io::Error) -> bool {
+    let Some(inner) = err.get_ref() else {
+        return false;
+    };
+    matches!(
+        inner.downcast_ref::<rustls::Error>(),
+        Some(rustls::Error::InvalidMessage(
+            rustls::InvalidMessage::InvalidContentType
+        ))
+    )
+}
+
+fn wrap_inner(parms: &Validated, sock: ServerSock) -> Result<ServerSock, ErrorSource> {
     // we should really cache this
     let config = Arc::new(rustls_platform_verifier::tls_config());
 
     let server_name = parms.connect_tcp.to_string();
-    let server_name = ServerName::try_from(server_name)?;
+    let server_name = ServerName::try_from(server_name).map_err(ErrorSource::new)?;
 
-    let client = ClientConnection::new(config, server_name)?;
+    let client = ClientConnection::new(config, server_name).map_err(ErrorSource::new)?;
 
     let stream = StreamOwned::new(client, sock);
     let wrapped = StreamWrapper(stream);
@@ -24,6 +24,10 @@ use super::{
 pub struct MapiBuf {
     buffer: Vec<u8>,
     block_left: usize,
+    /// Set once writing the message has started, holds the number of bytes
+    /// that have been written so far. No more data may be appended until the
+    /// buffer is reset.
+    sent: Option<usize>,
 }
 
 impl Default for MapiBuf {
@@ -42,6 +46,7 @@ impl MapiBuf {
         let mut me = MapiBuf {
             buffer,
             block_left: 0,
+            sent: None,
         };
         // obvious dummy header
         me.buffer.push(0xFF);
@@ -51,6 +56,7 @@ impl MapiBuf {
     }
 
     pub fn append(&mut self, data: impl AsRef<[u8]>) {
+        debug_assert!(self.sent.is_none(), "append to a message being written");
         let data = data.as_ref();
         if data.len() <= self.block_left {
             // happy path
@@ -93,16 +99,24 @@ impl MapiBuf {
         self.finish_block(true);
     }
 
-    pub fn reset(&mut self) -> &[u8] {
-        let raw_base = self.buffer.as_ptr();
-        let mut raw_len = self.buffer.len();
+    /// Number of bytes to send, leaving out the dummy header of a block that
+    /// hasn't been started yet.
+    fn wire_len(&self) -> usize {
         if self.block_left == BLOCKSIZE {
-            raw_len -= 2;
+            self.buffer.len() - 2
+        } else {
+            self.buffer.len()
         }
+    }
+
+    pub fn reset(&mut self) -> &[u8] {
+        let raw_base = self.buffer.as_ptr();
+        let raw_len = self.wire_len();
         // now reset the buffer but make sure not to overwrite the initial
         // header yet
         self.buffer.truncate(2);
         self.block_left = BLOCKSIZE;
+        self.sent = None;
         unsafe { std::slice::from_raw_parts(raw_base, raw_len) }
     }
 
@@ -111,9 +125,36 @@ impl MapiBuf {
         self.reset()
     }
 
-    pub fn write_reset<W: io::Write>(&mut self, mut wr: W) -> io::Result<W> {
-        let data = self.end_reset();
-        wr.write_all(data)?;
+    /// End the message and write it to `wr`. See
+    /// [`flush_reset()`](`MapiBuf::flush_reset`) for what happens if writing
+    /// fails.
+    pub fn write_reset<W: io::Write>(&mut self, wr: W) -> io::Result<W> {
+        if self.sent.is_none() {
+            self.end();
+        }
+        self.flush_reset(wr)
+    }
+
+    /// Write the contents of the buffer to `wr` as they are and reset it.
+    ///
+    /// The buffer is only reset after everything has been written. If writing
+    /// fails, the data that has not been written yet is kept and calling
+    /// [`write_reset()`](`MapiBuf::write_reset`) or this method again
+    /// continues where the previous attempt left off. Call
+    /// [`reset()`](`MapiBuf::reset`) to discard it instead.
+    pub fn flush_reset<W: io::Write>(&mut self, mut wr: W) -> io::Result<W> {
+        let len = self.wire_len();
+        let mut sent = self.sent.unwrap_or(0);
+        while sent < len {
+            self.sent = Some(sent);
+            match wr.write(&self.buffer[sent..len]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => sent += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.reset();
         Ok(wr)
     }
 
@@ -141,6 +182,8 @@ impl fmt::Write for MapiBuf {
 mod tests {
     use std::iter::{self, Map};
 
+    use itertools::Itertools;
+
     use crate::util::referencedata::ReferenceData;
 
     use super::*;
@@ -157,7 +200,7 @@ mod tests {
 
     #[test]
     fn test_complex() {
-        let aaa: Vec<u8> = iter::repeat(b'A').take(BLOCKSIZE).collect();
+        let aaa: Vec<u8> = vec![b'A'; BLOCKSIZE];
 
         let mut mb = MapiBuf::new();
         mb.append(b"12345");
@@ -176,4 +219,109 @@ mod tests {
         verifier.assert(actual);
         verifier.assert_end();
     }
+
+    /// Accepts `budget` bytes, then fails once with an Interrupted error and
+    /// once with a BrokenPipe error.
+    struct FlakyWriter {
+        written: Vec<u8>,
+        budget: usize,
+        failures: Vec<io::ErrorKind>,
+    }
+
+    impl io::Write for FlakyWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.budget == 0 {
+                if let Some(kind) = self.failures.pop() {
+                    return Err(kind.into());
+                }
+                self.budget = usize::MAX;
+            }
+            let n = buf.len().min(self.budget).min(1000);
+            self.written.extend_from_slice(&buf[..n]);
+            self.budget -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_partial_write() {
+        let message = "y".repeat(2 * BLOCKSIZE + 100);
+        let mut mb = MapiBuf::new();
+        mb.append(&message);
+        let expected = mb.end_reset().to_vec();
+
+        let mut writer = FlakyWriter {
+            written: vec![],
+            budget: BLOCKSIZE + 10,
+            failures: vec![io::ErrorKind::BrokenPipe, io::ErrorKind::Interrupted],
+        };
+        mb.append(&message);
+        let err = mb.write_reset(&mut writer).map(|_| ()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(writer.written, &expected[..BLOCKSIZE + 10]);
+
+        // retrying sends the rest, without ending the message a second time
+        mb.write_reset(&mut writer).unwrap();
+        assert_eq!(writer.written, expected);
+
+        // after which the buffer is ready for the next message
+        mb.append(b"AAA");
+        assert_eq!(mb.end_reset(), &[7, 0, b'A', b'A', b'A']);
+    }
+
+    #[test]
+    fn test_partial_write_discard() {
+        let mut writer = FlakyWriter {
+            written: vec![],
+            budget: 3,
+            failures: vec![io::ErrorKind::BrokenPipe],
+        };
+        let mut mb = MapiBuf::new();
+        mb.append(b"AAAAA");
+        assert!(mb.write_reset(&mut writer).is_err());
+        mb.reset();
+        mb.append(b"BBB");
+        mb.write_reset(&mut writer).unwrap();
+        assert_eq!(writer.written, &[11, 0, b'A', 7, 0, b'B', b'B', b'B']);
+    }
+
+    #[test]
+    fn test_multi_block() {
+        use crate::framing::reading::MapiReader;
+        use std::fmt::Write;
+
+        // A statement of more than two blocks, written in pieces that
+        // straddle the block boundaries, followed by one that ends exactly
+        // at a block boundary.
+        let values = (0..3000).map(|i| format!("({i})")).join(",");
+        let statement = format!("sINSERT INTO foo VALUES {values}\n;");
+        assert!(statement.len() > 2 * BLOCKSIZE);
+        let exact = "x".repeat(3 * BLOCKSIZE);
+
+        for message in [statement, exact] {
+            let mut mb = MapiBuf::new();
+            for piece in message.as_bytes().chunks(1000) {
+                write!(mb, "{}", std::str::from_utf8(piece).unwrap()).unwrap();
+            }
+            let wire = mb.end_reset().to_vec();
+
+            let mut refd = ReferenceData::new();
+            let mut chunks = message.as_bytes().chunks(BLOCKSIZE).peekable();
+            while let Some(chunk) = chunks.next() {
+                refd.data(Header::new(chunk.len(), chunks.peek().is_none()));
+                refd.data(chunk);
+            }
+            let mut verifier = refd.verifier();
+            verifier.assert(&wire);
+            verifier.assert_end();
+
+            let mut received = vec![];
+            MapiReader::to_end(wire.as_slice(), &mut received).unwrap();
+            assert_eq!(received, message.as_bytes());
+        }
+    }
 }
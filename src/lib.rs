@@ -16,14 +16,25 @@ pub mod convert;
 mod cursor;
 mod framing;
 pub mod monettypes;
+mod observer;
 pub mod parms;
+pub mod sqlgen;
 mod util;
 
-pub use conn::Connection;
-pub use cursor::{replies::ResultColumn, Cursor, CursorError, CursorResult};
-pub use framing::connecting::{ConnectError, ConnectResult};
+pub use conn::{ConnStats, Connection, Diagnostics};
+pub use convert::FromRow;
+pub use cursor::{
+    replies::{ReplyKind, ResultColumn},
+    Cursor, CursorError, CursorResult, RowIter,
+};
+pub use framing::connecting::{ConnectError, ConnectResult, Endian};
 pub use monettypes::MonetType;
+pub use observer::{QueryEvent, QueryEventKind, QueryObserver};
 pub use parms::Parameters;
+pub use util::errorsource::ErrorSource;
+
+#[cfg(feature = "derive")]
+pub use monetdb_derive::FromRow;
 
 /// The version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
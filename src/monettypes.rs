@@ -16,7 +16,8 @@ use std::fmt;
 pub type Precision = u8;
 
 /// Type alias for the scale (number of digits after the decimal point) of
-/// DECIMAL types.
+/// DECIMAL types, and for the number of decimals of the seconds of
+/// TIME and TIMESTAMP types.
 pub type Scale = u8;
 
 /// Type alias for the width of for example CHAR/VARCHAR types.
@@ -56,24 +57,20 @@ pub enum MonetType {
     DayInterval,
     /// 64 bit signed number of milliseconds.
     SecInterval,
-    /// 24-hour time of day HH:MM:SS.sss with varying number of decimals,
+    /// 24-hour time of day HH:MM:SS.sss with the given number of decimals,
     /// independent of time zone.
-    /// (Nr of decimals currently unimplemented.)
-    Time,
-    /// 24-hour time of day HH:MM:SS.sss with varying number of decimals,
+    Time(Scale),
+    /// 24-hour time of day HH:MM:SS.sss with the given number of decimals,
     /// expressed in the connections current timezone.
-    /// (Nr of decimals currently unimplemented.)
-    TimeTz,
+    TimeTz(Scale),
     /// Gregorian calendar date YYYY-MM-DD
     Date,
-    /// Timestamp YYYY-MM-DD HH:MM:SS.sss with varying number of decimals,
+    /// Timestamp YYYY-MM-DD HH:MM:SS.sss with the given number of decimals,
     /// expressed in the connections current timezone.
-    /// (Nr of decimals currently unimplemented.)
-    Timestamp,
-    /// Timestamp YYYY-MM-DD HH:MM:SS.sss with varying number of decimals,
+    Timestamp(Scale),
+    /// Timestamp YYYY-MM-DD HH:MM:SS.sss with the given number of decimals,
     /// independent of time zone.
-    /// (Nr of decimals currently unimplemented.)
-    TimestampTz,
+    TimestampTz(Scale),
     Blob,
     /// A URL.
     Url,
@@ -82,6 +79,9 @@ pub enum MonetType {
     Json,
     /// A UUID.
     Uuid,
+    /// A GEOMETRY value, available when the server has the geom module.
+    /// Also used for GEOMETRYA, arrays of geometries.
+    Geometry,
 }
 
 impl fmt::Display for MonetType {
@@ -102,21 +102,105 @@ impl fmt::Display for MonetType {
             MonthInterval => f.write_str("MONTH_INTERVAL"),
             DayInterval => f.write_str("DAY_INTERVAL"),
             SecInterval => f.write_str("SEC_INTERVAL"),
-            Time => f.write_str("TIME"),
-            TimeTz => f.write_str("TIMETZ"),
+            Time(s) => write!(f, "TIME({s})"),
+            TimeTz(s) => write!(f, "TIMETZ({s})"),
             Date => f.write_str("DATE"),
-            Timestamp => f.write_str("TIMESTAMP"),
-            TimestampTz => f.write_str("TIMESTAMPTZ"),
+            Timestamp(s) => write!(f, "TIMESTAMP({s})"),
+            TimestampTz(s) => write!(f, "TIMESTAMPTZ({s})"),
             Blob => f.write_str("BLOB"),
             Url => f.write_str("URL"),
             Inet => f.write_str("INET"),
             Json => f.write_str("JSON"),
             Uuid => f.write_str("UUID"),
+            Geometry => f.write_str("GEOMETRY"),
         }
     }
 }
 
 impl MonetType {
+    /// Returns true if values of this type are plain numbers: the integer
+    /// types, DECIMAL, REAL and DOUBLE.
+    ///
+    /// OID is not included because MonetDB renders it as for example `42@0`.
+    /// The interval types are not included either, they are considered temporal.
+    pub fn is_numeric(&self) -> bool {
+        use MonetType::*;
+        matches!(
+            self,
+            TinyInt | SmallInt | Int | BigInt | HugeInt | Decimal(..) | Real | Double
+        )
+    }
+
+    /// Returns true if this is the BOOLEAN type.
+    pub fn is_bool(&self) -> bool {
+        matches!(self, MonetType::Bool)
+    }
+
+    /// For the TIME and TIMESTAMP types, return the number of decimals of the
+    /// seconds, for example 3 for `TIME(3)`. Returns `None` for other types.
+    /// If the server did not send the precision, this is 6.
+    ///
+    /// This indicates whether trailing zeroes in the fractional seconds are
+    /// significant, see also [`RawTime::with_precision`][`crate::convert::raw_temporal::RawTime::with_precision`].
+    pub fn fractional_digits(&self) -> Option<Scale> {
+        use MonetType::*;
+        match self {
+            Time(s) | TimeTz(s) | Timestamp(s) | TimestampTz(s) => Some(*s),
+            _ => None,
+        }
+    }
+
+    /// Return the maximum number of characters the server can send for a
+    /// value of this type in the text protocol, not counting quotes and
+    /// escapes. Returns `None` if the length is unbounded, as for BLOB, JSON,
+    /// URL, GEOMETRY and VARCHAR without a width.
+    ///
+    /// For example VARCHAR(n) gives n, INT gives 11 (`-2147483647`), BOOLEAN
+    /// gives 5 (`false`) and DECIMAL(p, s) gives p + 2 for the sign and the
+    /// decimal point, or p + 3 if there is also a leading zero because s equals
+    /// p. This can be used to pre-allocate buffers or to align columns.
+    pub fn max_text_width(&self) -> Option<usize> {
+        use MonetType::*;
+        // years range from -4712 to 170049
+        const DATE: usize = "170049-12-31".len();
+        const TIME: usize = "23:59:59".len();
+        const TZ: usize = "+14:00".len();
+        // the number of seconds with three decimals
+        const INTERVAL: usize = "-9223372036854775.807".len();
+        let fraction = |s: Scale| if s > 0 { 1 + s as usize } else { 0 };
+        let width = match *self {
+            Bool => "false".len(),
+            TinyInt => "-127".len(),
+            SmallInt => "-32767".len(),
+            Int | MonthInterval => "-2147483647".len(),
+            BigInt => "-9223372036854775807".len(),
+            HugeInt => "-170141183460469231731687303715884105727".len(),
+            Oid => "9223372036854775807@0".len(),
+            Decimal(p, s) => 1 + (p.saturating_sub(s) as usize).max(1) + fraction(s),
+            Varchar(0) => return None,
+            Varchar(n) => n as usize,
+            Real => "-1.17549435e-38".len(),
+            Double => "-2.2250738585072014e-308".len(),
+            DayInterval | SecInterval => INTERVAL,
+            Time(s) => TIME + fraction(s),
+            TimeTz(s) => TIME + fraction(s) + TZ,
+            Date => DATE,
+            Timestamp(s) => DATE + 1 + TIME + fraction(s),
+            TimestampTz(s) => DATE + 1 + TIME + fraction(s) + TZ,
+            Inet => "255.255.255.255/32".len(),
+            Uuid => "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx".len(),
+            Blob | Url | Json | Geometry => return None,
+        };
+        Some(width)
+    }
+
+    /// Returns true for the types whose values the server sends as quoted
+    /// strings in the text protocol.
+    pub(crate) fn is_quoted_in_text(&self) -> bool {
+        use MonetType::*;
+        matches!(self, Varchar(_) | Url | Json)
+    }
+
     /// Used while parsing result sets. Based on the name
     /// create a MonetType instance with parameters
     /// set to a dummy value.
@@ -137,18 +221,45 @@ impl MonetType {
             "month_interval" => MonthInterval,
             "day_interval" => DayInterval,
             "sec_interval" => SecInterval,
-            "time" => Time,
-            "timetz" => TimeTz,
+            // The typesizes header corrects the number of decimals. Without
+            // it, assume the server's maximum precision, microseconds.
+            "time" => Time(6),
+            "timetz" => TimeTz(6),
             "date" => Date,
-            "timestamp" => Timestamp,
-            "timestamptz" => TimestampTz,
+            "timestamp" => Timestamp(6),
+            "timestamptz" => TimestampTz(6),
             "blob" => Blob,
             "url" => Url,
             "inet" => Inet,
             "json" => Json,
             "uuid" => Uuid,
+            "geometry" | "geometrya" => Geometry,
             _ => return None,
         };
         Some(typ)
     }
 }
+
+#[test]
+fn test_max_text_width() {
+    use MonetType::*;
+    assert_eq!(Bool.max_text_width(), Some(5));
+    assert_eq!(TinyInt.max_text_width(), Some(4));
+    assert_eq!(Int.max_text_width(), Some(11));
+    assert_eq!(HugeInt.max_text_width(), Some(40));
+    assert_eq!(Varchar(12).max_text_width(), Some(12));
+    assert_eq!(Varchar(0).max_text_width(), None);
+    assert_eq!(Blob.max_text_width(), None);
+    assert_eq!(Json.max_text_width(), None);
+
+    // -12345.67, -1234567 and -0.123
+    assert_eq!(Decimal(7, 2).max_text_width(), Some(9));
+    assert_eq!(Decimal(7, 0).max_text_width(), Some(8));
+    assert_eq!(Decimal(3, 3).max_text_width(), Some(6));
+
+    // 12:34:56, 12:34:56.789+01:00 and 170049-12-31 23:59:59.999999
+    assert_eq!(Time(0).max_text_width(), Some(8));
+    assert_eq!(TimeTz(3).max_text_width(), Some(18));
+    assert_eq!(Timestamp(6).max_text_width(), Some(28));
+    assert_eq!(Uuid.max_text_width(), Some(36));
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+//! Hook to observe the queries executed on a connection, for example to feed
+//! them into a metrics or tracing system.
+
+use std::{sync::Arc, time::Duration};
+
+use crate::CursorError;
+
+/// A callback that receives a [`QueryEvent`] for every query executed on a
+/// connection, see
+/// [`Connection::set_query_observer()`](`crate::Connection::set_query_observer`).
+pub type QueryObserver = Box<dyn Fn(&QueryEvent) + Send + Sync>;
+
+/// How connections and cursors hold on to a [`QueryObserver`].
+pub(crate) type SharedQueryObserver = Arc<dyn Fn(&QueryEvent) + Send + Sync>;
+
+/// What a [`QueryEvent`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueryEventKind {
+    /// The server has replied to
+    /// [`Cursor::execute()`](`crate::Cursor::execute`) or one of its variants,
+    /// or sending the statements failed.
+    Executed,
+    /// [`Cursor::next_row()`](`crate::Cursor::next_row`) has reached the end
+    /// of a result set.
+    ResultSetDone,
+}
+
+/// Information passed to a [`QueryObserver`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct QueryEvent<'a> {
+    pub kind: QueryEventKind,
+    /// The statements that were executed. Long string literals are replaced
+    /// with `'***'` and long statements are truncated.
+    pub sql: &'a str,
+    /// Time since the statements were sent to the server.
+    pub duration: Duration,
+    /// For [`Executed`](`QueryEventKind::Executed`), the number of affected
+    /// rows of the first reply if the server reported one.
+    pub affected_rows: Option<i64>,
+    /// For [`Executed`](`QueryEventKind::Executed`), the number of rows in the
+    /// result set if the first reply is a result set. For
+    /// [`ResultSetDone`](`QueryEventKind::ResultSetDone`), the number of rows
+    /// retrieved from the result set.
+    pub result_rows: Option<u64>,
+    /// The error, if executing the statements failed.
+    pub error: Option<&'a CursorError>,
+}
@@ -27,13 +27,18 @@
 //! knows how to make a number of policy decisions, such as whether to connect to
 //! a Unix Domain socket, a TCP socket or both.
 mod parameters;
+#[cfg(feature = "serde")]
+mod serialize;
 mod urlparser;
 #[cfg(test)]
 mod urltests;
 
 use std::{borrow::Cow, fmt, str::FromStr};
 
+pub(crate) use parameters::render_bool;
 pub use parameters::{parse_bool, Parameters, Parm, TlsVerify, Validated, Value, PARM_TABLE_SIZE};
+#[cfg(feature = "serde")]
+pub use serialize::SerializableParameters;
 
 /// An error that occurs while dealing with [`Parameters`].
 #[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
@@ -80,6 +85,21 @@ pub enum ParmError {
     /// The given parameter is not allowed to contain newlines.
     #[error("parameter: '{0}': must not contain newlines")]
     ClientInfoNewline(Parm),
+    /// The given parameter contains a character that would corrupt the login
+    /// handshake, such as a colon in the user name or a control character.
+    #[error("parameter '{0}' must not contain {1:?}")]
+    ForbiddenCharacter(Parm, char),
+    /// The init statement with the given index, see
+    /// [`Parameters::add_init_statement()`], contains a NUL character.
+    #[error("init statement {0} contains a NUL character")]
+    InvalidInitStatement(usize),
+    /// A [`Value`] could not be converted to the requested type, see for
+    /// example `TryFrom<&Value> for bool`.
+    #[error("cannot convert '{value}' to {expected}")]
+    ValueConversion {
+        value: String,
+        expected: &'static str,
+    },
 }
 
 pub type ParmResult<T> = Result<T, ParmError>;
@@ -12,6 +12,9 @@ use std::time::Duration;
 
 use urlparser::{is_our_url, parse_any_url, url_from_parms};
 
+use crate::convert::local_timezone_seconds;
+use crate::framing::connecting::Endian;
+
 use super::*;
 
 type Cowstr = Cow<'static, str>;
@@ -62,17 +65,36 @@ pub enum Parm {
     // Specific to this crate
     #[enumeration(rename = "connect_timeout")]
     ConnectTimeout,
+    #[enumeration(rename = "query_timeout")]
+    QueryTimeout,
+    #[enumeration(rename = "slow_query_log_ms")]
+    SlowQueryLogMs,
+    #[enumeration(rename = "max_result_bytes")]
+    MaxResultBytes,
     #[enumeration(rename = "client_info")]
     ClientInfo,
     #[enumeration(rename = "client_application")]
     ClientApplication,
     #[enumeration(rename = "client_remark")]
     ClientRemark,
+    #[enumeration(rename = "client_library")]
+    ClientLibrary,
+    #[enumeration(rename = "prefer_unix")]
+    PreferUnix,
+    #[enumeration(rename = "fetch_metadata_on_connect")]
+    FetchMetadataOnConnect,
+    #[enumeration(rename = "suppress_handshake_options")]
+    SuppressHandshakeOptions,
+
+    Hash,
+    /// Byte order to announce to the server, for testing only.
+    #[doc(hidden)]
+    #[enumeration(rename = "client_endian")]
+    ClientEndian,
 
     // Unused but recognized to pass the tests
     TableSchema,
     Table,
-    Hash,
     Debug,
     Logfile,
     MaxPrefetch,
@@ -101,12 +123,20 @@ impl Parm {
             Parm::SockDir => "sockdir",
             Parm::Timezone => "timezone",
             Parm::ConnectTimeout => "connect_timeout",
+            Parm::QueryTimeout => "query_timeout",
+            Parm::SlowQueryLogMs => "slow_query_log_ms",
+            Parm::MaxResultBytes => "max_result_bytes",
             Parm::ClientInfo => "client_info",
             Parm::ClientApplication => "client_application",
             Parm::ClientRemark => "client_remark",
+            Parm::ClientLibrary => "client_library",
+            Parm::PreferUnix => "prefer_unix",
+            Parm::FetchMetadataOnConnect => "fetch_metadata_on_connect",
+            Parm::SuppressHandshakeOptions => "suppress_handshake_options",
             Parm::TableSchema => "tableschema",
             Parm::Table => "table",
             Parm::Hash => "hash",
+            Parm::ClientEndian => "client_endian",
             Parm::Debug => "debug",
             Parm::Logfile => "logfile",
             Parm::MaxPrefetch => "maxprefetch",
@@ -154,8 +184,9 @@ impl Parm {
         use Parm::*;
         use ParmType::*;
         match self {
-            Tls | Autocommit | ClientInfo => Bool,
-            Port | ReplySize | Timezone | MaxPrefetch | ConnectTimeout => Int,
+            Tls | Autocommit | ClientInfo | PreferUnix | FetchMetadataOnConnect => Bool,
+            Port | ReplySize | Timezone | MaxPrefetch | ConnectTimeout | QueryTimeout
+            | SlowQueryLogMs | MaxResultBytes => Int,
             _ => Str,
         }
     }
@@ -167,7 +198,7 @@ impl Parm {
 
     #[allow(dead_code)]
     pub(crate) fn require_int(&self) -> bool {
-        matches!(self, Parm::Port | Parm::ReplySize | Parm::Timezone)
+        matches!(self, Parm::Port | Parm::ReplySize)
     }
 }
 
@@ -198,12 +229,29 @@ fn test_parm_names() {
     assert_eq!(Parm::from_str("sockdir"), Ok(Parm::SockDir));
     assert_eq!(Parm::from_str("timezone"), Ok(Parm::Timezone));
     assert_eq!(Parm::from_str("connect_timeout"), Ok(Parm::ConnectTimeout));
+    assert_eq!(Parm::from_str("query_timeout"), Ok(Parm::QueryTimeout));
+    assert_eq!(
+        Parm::from_str("slow_query_log_ms"),
+        Ok(Parm::SlowQueryLogMs)
+    );
+    assert_eq!(Parm::from_str("max_result_bytes"), Ok(Parm::MaxResultBytes));
     assert_eq!(Parm::from_str("client_info"), Ok(Parm::ClientInfo));
     assert_eq!(
         Parm::from_str("client_application"),
         Ok(Parm::ClientApplication)
     );
     assert_eq!(Parm::from_str("client_remark"), Ok(Parm::ClientRemark));
+    assert_eq!(Parm::from_str("client_library"), Ok(Parm::ClientLibrary));
+    assert_eq!(Parm::from_str("prefer_unix"), Ok(Parm::PreferUnix));
+    assert_eq!(
+        Parm::from_str("fetch_metadata_on_connect"),
+        Ok(Parm::FetchMetadataOnConnect)
+    );
+    assert_eq!(
+        Parm::from_str("suppress_handshake_options"),
+        Ok(Parm::SuppressHandshakeOptions)
+    );
+    assert_eq!(Parm::from_str("client_endian"), Ok(Parm::ClientEndian));
     // special case
     assert_eq!(Parm::from_str("fetchsize"), Ok(Parm::ReplySize));
 
@@ -315,6 +363,11 @@ impl Value {
     /// For example, it can only be assigned to [`Parm::Autocommit`]
     /// if it's a boolean or can be converted to a boolean.
     pub fn verify_assign(&self, parm: Parm) -> ParmResult<()> {
+        // the time zone can also be 'local' or the name of a zone, these are
+        // checked by Validated
+        if parm == Parm::Timezone && matches!(self, Value::Str(_)) {
+            return Ok(());
+        }
         let parm_type = parm.parm_type();
         // in most cases we check if the value can be converted,
         // but for strings we check if it's the actual variant
@@ -423,10 +476,97 @@ impl From<usize> for Value {
     }
 }
 
+/// Uses [`Value::bool_value()`], so strings such as "yes" and "off" are
+/// accepted as well.
+impl TryFrom<&Value> for bool {
+    type Error = ParmError;
+
+    fn try_from(value: &Value) -> ParmResult<bool> {
+        value
+            .bool_value()
+            .ok_or_else(|| ParmError::ValueConversion {
+                value: value.to_string(),
+                expected: "bool",
+            })
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ParmError;
+
+    fn try_from(value: Value) -> ParmResult<bool> {
+        bool::try_from(&value)
+    }
+}
+
+/// Uses [`Value::int_value()`], so strings containing a number are accepted
+/// as well.
+impl TryFrom<&Value> for i64 {
+    type Error = ParmError;
+
+    fn try_from(value: &Value) -> ParmResult<i64> {
+        value.int_value().ok_or_else(|| ParmError::ValueConversion {
+            value: value.to_string(),
+            expected: "integer",
+        })
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ParmError;
+
+    fn try_from(value: Value) -> ParmResult<i64> {
+        i64::try_from(&value)
+    }
+}
+
+/// Uses [`Value::str_value()`]. Every value can be rendered as a string so
+/// this never fails, it exists so all three types can be converted the same
+/// way.
+impl TryFrom<&Value> for String {
+    type Error = ParmError;
+
+    fn try_from(value: &Value) -> ParmResult<String> {
+        Ok(value.str_value().into_owned())
+    }
+}
+
+/// Like `TryFrom<&Value>` but avoids copying if the value is a string.
+impl TryFrom<Value> for String {
+    type Error = ParmError;
+
+    fn try_from(value: Value) -> ParmResult<String> {
+        Ok(value.into_str().into_owned())
+    }
+}
+
+#[test]
+fn test_value_try_from() {
+    let yes = Value::from("yes");
+    let num = Value::from("42");
+    assert_eq!(bool::try_from(&yes), Ok(true));
+    assert_eq!(bool::try_from(Value::Bool(false)), Ok(false));
+    assert_eq!(
+        bool::try_from(&num),
+        Err(ParmError::ValueConversion {
+            value: "42".to_string(),
+            expected: "bool"
+        })
+    );
+    let n: i64 = (&num).try_into().unwrap();
+    assert_eq!(n, 42);
+    assert_eq!(i64::try_from(Value::Int(-1)), Ok(-1));
+    let err = i64::try_from(&yes).unwrap_err();
+    assert_eq!(err.to_string(), "cannot convert 'yes' to integer");
+    assert_eq!(String::try_from(&num).as_deref(), Ok("42"));
+    assert_eq!(String::try_from(Value::Int(7)).as_deref(), Ok("7"));
+    assert_eq!(String::try_from(Value::Bool(true)).as_deref(), Ok("on"));
+}
+
 /// If you want to create a table indexed by [`Parm`], the table must
 /// have at least this number of elements. Use [`Parm::index`] to convert
 /// Parms to usizes.
-pub const PARM_TABLE_SIZE: usize = 30;
+pub const PARM_TABLE_SIZE: usize = 36;
 
 #[test]
 fn test_parm_table_size() {
@@ -453,6 +593,7 @@ pub struct Parameters {
     user_changed: bool,
     password_changed: bool,
     timezone_set: bool,
+    init_statements: Vec<String>,
 }
 
 impl Default for Parameters {
@@ -471,6 +612,7 @@ pub const DEFAULT_PARAMETERS: Parameters = {
         user_changed: false,
         password_changed: false,
         timezone_set: false,
+        init_statements: Vec::new(),
     }
 };
 
@@ -502,8 +644,10 @@ const fn default_parameter_value_by_index(idx: usize) -> Value {
         Value::Int(200)
     } else if idx == Binary.index() {
         Value::from_static("on") // we can't yet, but we'd like to
-    } else if idx == ClientInfo.index() {
+    } else if idx == ClientInfo.index() || idx == PreferUnix.index() {
         Value::Bool(true)
+    } else if idx == FetchMetadataOnConnect.index() {
+        Value::Bool(false)
     } else {
         Value::from_static("")
     }
@@ -627,6 +771,96 @@ impl Parameters {
         }
     }
 
+    /// Returns whether the given Parm must be included when rendering a URL.
+    /// This is the case if it does not have its default value, and for the
+    /// time zone also if it has been set explicitly, because that determines
+    /// [`Validated::connect_timezone_seconds`].
+    pub(crate) fn is_explicit(&self, parm: Parm) -> bool {
+        !self.is_default(parm) || (parm == Parm::Timezone && self.timezone_set)
+    }
+
+    /// Iterate over the Parms for which [`Parameters::is_explicit`] holds, in
+    /// declaration order.
+    #[cfg(feature = "serde")]
+    pub(crate) fn explicit_parms(&self) -> impl Iterator<Item = Parm> + '_ {
+        Parm::iter().filter(|&p| self.is_explicit(p))
+    }
+
+    /// Return the Parms whose values differ between `self` and `other`,
+    /// together with the value in `self` and the value in `other`.
+    ///
+    /// Values are compared the same way as in [`Parameters::is_default`], so
+    /// for example `"on"` and `true` are considered equal. The values of
+    /// [sensitive](`Parm::is_sensitive`) parameters such as the password are
+    /// replaced with `"***"`.
+    pub fn diff(&self, other: &Parameters) -> Vec<(Parm, Value, Value)> {
+        let mut differences = vec![];
+        for parm in Parm::iter() {
+            let left = self.get(parm);
+            let right = other.get(parm);
+            if equivalent(parm, left, right) {
+                continue;
+            }
+            if parm.is_sensitive() {
+                let redacted = Value::from_static("***");
+                differences.push((parm, redacted.clone(), redacted));
+            } else {
+                differences.push((parm, left.clone(), right.clone()));
+            }
+        }
+        differences
+    }
+
+    /// Fix up values that are obviously wrong but whose intent is clear,
+    /// returning the Parms that were changed together with the old and the
+    /// new value. Unlike [`validate()`](`Parameters::validate`), which rejects
+    /// invalid values, this is meant for tools that want to be tolerant of
+    /// sloppy configuration, for example by logging the adjustments as
+    /// warnings.
+    ///
+    /// Exactly these changes are made:
+    ///
+    /// * leading and trailing whitespace is removed from `host`, `database`,
+    ///   `schema`, `sock` and `sockdir`,
+    /// * a `port` of 0 is replaced with -1, the default, which means 50000,
+    /// * a negative `replysize` is replaced with 0, which means the same:
+    ///   no limit,
+    /// * a `replysize` larger than 1,000,000 is reduced to 1,000,000.
+    ///
+    /// Other Parms, including user name and password, are left alone, as
+    /// are values that are not even of the right type.
+    pub fn sanitize(&mut self) -> Vec<(Parm, Value, Value)> {
+        const MAX_REPLY_SIZE: i64 = 1_000_000;
+
+        let mut adjustments = vec![];
+        let mut adjust = |parms: &mut Parameters, parm: Parm, new: Value| {
+            let old = parms.replace(parm, new.clone()).unwrap();
+            adjustments.push((parm, old, new));
+        };
+
+        use Parm::*;
+        for parm in [Host, Database, Schema, Sock, SockDir] {
+            let value = self.get_str(parm).unwrap();
+            let trimmed = value.trim();
+            if trimmed.len() != value.len() {
+                let trimmed = Value::from(trimmed);
+                adjust(self, parm, trimmed);
+            }
+        }
+
+        if self.get(Port).int_value() == Some(0) {
+            adjust(self, Port, Value::Int(-1));
+        }
+
+        match self.get(ReplySize).int_value() {
+            Some(n) if n < 0 => adjust(self, ReplySize, Value::Int(0)),
+            Some(n) if n > MAX_REPLY_SIZE => adjust(self, ReplySize, Value::Int(MAX_REPLY_SIZE)),
+            _ => {}
+        }
+
+        adjustments
+    }
+
     /// If exactly one of user name and password has been set since
     /// the previous call to this method, clear the other.
     pub fn boundary(&mut self) {
@@ -639,6 +873,21 @@ impl Parameters {
         self.password_changed = false;
     }
 
+    /// Return a copy of these parameters with the
+    /// [sensitive](`Parm::is_sensitive`) parameters, user name and password,
+    /// reset to their defaults.
+    ///
+    /// Credentials set on the copy afterwards are handled by
+    /// [`Parameters::boundary`] as if they were set on a fresh object.
+    pub fn without_credentials(&self) -> Parameters {
+        let mut parms = self.clone();
+        for parm in Parm::iter().filter(Parm::is_sensitive) {
+            parms.reset(parm);
+        }
+        parms.boundary();
+        parms
+    }
+
     /// Overwrite Parms with values found in the given URL.
     ///
     /// Supports `monetdb://`, `monetdbs://` and `mapi:monetdb://` URLs.
@@ -656,6 +905,249 @@ impl Parameters {
     }
 }
 
+#[test]
+fn test_client_info_newline() {
+    let parms = Parameters::default()
+        .with_client_library("my-lib\n1.0")
+        .unwrap();
+    assert_eq!(
+        parms.validate().unwrap_err(),
+        ParmError::ClientInfoNewline(Parm::ClientLibrary)
+    );
+
+    let parms = parms.with_client_info("off").unwrap();
+    assert!(parms.validate().is_ok());
+}
+
+#[test]
+fn test_forbidden_characters() {
+    let validate = |parm: Parm, value: &str| {
+        let mut parms = Parameters::default();
+        parms.set(parm, value).unwrap();
+        parms.validate().map(|_| ())
+    };
+
+    assert_eq!(
+        validate(Parm::User, "me:you"),
+        Err(ParmError::ForbiddenCharacter(Parm::User, ':'))
+    );
+    assert_eq!(
+        validate(Parm::User, "me\n"),
+        Err(ParmError::ForbiddenCharacter(Parm::User, '\n'))
+    );
+    assert_eq!(
+        validate(Parm::Schema, "a\tb"),
+        Err(ParmError::ForbiddenCharacter(Parm::Schema, '\t'))
+    );
+    assert_eq!(validate(Parm::Schema, "a:b"), Ok(()));
+    // the password is hashed before it is sent
+    assert_eq!(validate(Parm::Password, "se:cr\net"), Ok(()));
+    let err = validate(Parm::User, "me:you").unwrap_err();
+    assert_eq!(err.to_string(), "parameter 'user' must not contain ':'");
+}
+
+#[test]
+fn test_init_statements() {
+    let mut parms = Parameters::default()
+        .with_init_statement("SET optimizer = 'minimal_pipe'")
+        .with_init_statement("SET ROLE analyst");
+    assert_eq!(
+        parms.validate().unwrap().init_statements,
+        ["SET optimizer = 'minimal_pipe'", "SET ROLE analyst"]
+    );
+
+    parms.add_init_statement("SELECT '\0'");
+    assert_eq!(
+        parms.validate().unwrap_err(),
+        ParmError::InvalidInitStatement(2)
+    );
+
+    parms.clear_init_statements();
+    assert!(parms.init_statements().is_empty());
+}
+
+#[test]
+fn test_client_endian() {
+    let parms = Parameters::default();
+    assert_eq!(parms.validate().unwrap().client_endian, Endian::NATIVE);
+    let parms = parms.with_client_endian("big").unwrap();
+    assert_eq!(parms.validate().unwrap().client_endian, Endian::Big);
+    let parms = parms.with_client_endian("LIT").unwrap();
+    assert_eq!(parms.validate().unwrap().client_endian, Endian::Lit);
+    let parms = parms.with_client_endian("middle").unwrap();
+    assert_eq!(
+        parms.validate().unwrap_err(),
+        ParmError::InvalidValue(Parm::ClientEndian)
+    );
+}
+
+/// Compare two values the way they will be interpreted for the given Parm.
+fn equivalent(parm: Parm, left: &Value, right: &Value) -> bool {
+    if left == right {
+        return true;
+    }
+    if parm == Parm::Binary {
+        return left.binary_value() == right.binary_value();
+    }
+    match parm.parm_type() {
+        ParmType::Bool => left.bool_value() == right.bool_value(),
+        ParmType::Int => match (left.int_value(), right.int_value()) {
+            (None, None) => left.str_value() == right.str_value(),
+            (l, r) => l == r,
+        },
+        ParmType::Str => left.str_value() == right.str_value(),
+    }
+}
+
+#[test]
+fn test_diff() {
+    let left = Parameters::basic("demo", "alice", "secret")
+        .unwrap()
+        .with_autocommit(false)
+        .unwrap();
+    assert_eq!(left.diff(&left.clone()), vec![]);
+
+    let mut right = left.clone();
+    right.set(Parm::Autocommit, "off").unwrap();
+    right.set(Parm::Binary, "true").unwrap();
+    assert_eq!(left.diff(&right), vec![]);
+
+    right.set(Parm::Database, "other").unwrap();
+    right.set(Parm::Port, 50001).unwrap();
+    right.set(Parm::Password, "hunter2").unwrap();
+    let redacted = Value::from_static("***");
+    assert_eq!(
+        left.diff(&right),
+        vec![
+            (Parm::Database, Value::from("demo"), Value::from("other")),
+            (Parm::Port, Value::Int(-1), Value::Int(50001)),
+            (Parm::Password, redacted.clone(), redacted),
+        ]
+    );
+}
+
+#[test]
+fn test_sanitize() {
+    let mut parms = Parameters::from_url("monetdb://localhost:0/demo?replysize=-5").unwrap();
+    parms.set(Parm::Host, " db.example.com\n").unwrap();
+    parms.set(Parm::Schema, "\tsys").unwrap();
+    assert!(parms.validate().is_err());
+
+    let adjustments = parms.sanitize();
+    assert_eq!(
+        adjustments,
+        vec![
+            (
+                Parm::Host,
+                Value::from(" db.example.com\n"),
+                Value::from("db.example.com")
+            ),
+            (Parm::Schema, Value::from("\tsys"), Value::from("sys")),
+            (Parm::Port, Value::Int(0), Value::Int(-1)),
+            (Parm::ReplySize, Value::from("-5"), Value::Int(0)),
+        ]
+    );
+    let validated = parms.validate().unwrap();
+    assert_eq!(validated.connect_port, 50000);
+    assert_eq!(validated.database, "demo");
+
+    // nothing left to do
+    assert_eq!(parms.sanitize(), vec![]);
+
+    let mut parms = Parameters::default().with_replysize(i64::MAX).unwrap();
+    parms.set(Parm::Password, " secret ").unwrap();
+    assert_eq!(
+        parms.sanitize(),
+        vec![(Parm::ReplySize, Value::Int(i64::MAX), Value::Int(1_000_000))]
+    );
+    assert_eq!(parms.get_str(Parm::Password).unwrap(), " secret ");
+}
+
+#[test]
+fn test_unix_fallback() {
+    let fallback = |dir, port| Validated::unix_fallback(dir, "/tmp/.s.monetdb.50000", port);
+    assert_eq!(
+        fallback("/run/user/1000", 50000).as_deref(),
+        Some("/run/user/1000/.s.monetdb.50000")
+    );
+    assert_eq!(
+        fallback("/run/user/1000", 12345).as_deref(),
+        Some("/run/user/1000/.s.monetdb.12345")
+    );
+    // no runtime dir, or the same as the primary
+    assert_eq!(fallback("", 50000), None);
+    assert_eq!(fallback("/tmp", 50000), None);
+
+    // an explicit sockdir or sock is used as-is
+    let parms = Parameters::default().with_database("demo").unwrap();
+    let validated = parms.validate().unwrap();
+    assert_eq!(validated.connect_unix, "/tmp/.s.monetdb.50000");
+    let parms = parms.with_sockdir("/var/run").unwrap();
+    assert_eq!(parms.validate().unwrap().connect_unix_fallback, None);
+    let parms = Parameters::default().with_sock("/a/path").unwrap();
+    assert_eq!(parms.validate().unwrap().connect_unix_fallback, None);
+}
+
+#[test]
+fn test_timezone_names() {
+    let tz = |parms: &Parameters| parms.validate().map(|v| v.connect_timezone_seconds);
+
+    let parms = Parameters::from_url("monetdb:///demo?timezone=-90").unwrap();
+    assert_eq!(tz(&parms), Ok(Some(-5400)));
+
+    // the same offset as when the time zone has not been set
+    let parms = Parameters::from_url("monetdb:///demo?timezone=local").unwrap();
+    assert_eq!(tz(&parms), Ok(Some(local_timezone_seconds())));
+    assert!(parms
+        .url_without_credentials()
+        .unwrap()
+        .contains("timezone=local"));
+
+    let parms = parms.with_timezone_name("No/Such_Zone").unwrap();
+    assert_eq!(tz(&parms), Err(ParmError::InvalidValue(Parm::Timezone)));
+    let parms = parms.with_timezone_name("").unwrap();
+    assert_eq!(tz(&parms), Err(ParmError::InvalidValue(Parm::Timezone)));
+    let parms = parms.with_timezone_name("../../etc/passwd").unwrap();
+    assert_eq!(tz(&parms), Err(ParmError::InvalidValue(Parm::Timezone)));
+    let parms = parms.with_timezone_name("/etc/localtime").unwrap();
+    assert_eq!(tz(&parms), Err(ParmError::InvalidValue(Parm::Timezone)));
+
+    // only if the system has a time zone database. The sign of the Etc
+    // zones is inverted.
+    let parms = parms.with_timezone_name("Etc/GMT-3").unwrap();
+    if cfg!(not(feature = "tz-rs")) {
+        assert_eq!(tz(&parms), Err(ParmError::InvalidValue(Parm::Timezone)));
+    } else if std::path::Path::new("/usr/share/zoneinfo/Etc/GMT-3").exists() {
+        assert_eq!(tz(&parms), Ok(Some(3 * 3600)));
+    }
+
+    let amsterdam = Parameters::default()
+        .with_timezone_name("Europe/Amsterdam")
+        .unwrap();
+    let new_york = Parameters::default()
+        .with_timezone_name("America/New_York")
+        .unwrap();
+    assert_eq!(amsterdam.diff(&new_york).len(), 1);
+    assert!(amsterdam.diff(&amsterdam.clone()).is_empty());
+}
+
+#[test]
+fn test_without_credentials() {
+    let parms = Parameters::basic("demo", "alice", "secret").unwrap();
+    let mut template = parms.without_credentials();
+    assert!(template.is_default(Parm::User));
+    assert!(template.is_default(Parm::Password));
+    assert_eq!(template.get_str(Parm::Database).unwrap(), "demo");
+    assert_eq!(parms.get_str(Parm::User).unwrap(), "alice");
+
+    // credentials can be filled in again
+    template.set(Parm::User, "bob").unwrap();
+    template.set(Parm::Password, "hunter2").unwrap();
+    template.boundary();
+    assert_eq!(template.get_str(Parm::User).unwrap(), "bob");
+    assert_eq!(template.get_str(Parm::Password).unwrap(), "hunter2");
+}
+
 // Builder pattern
 impl Parameters {
     pub fn set_database(&mut self, value: &str) -> ParmResult<()> {
@@ -712,6 +1204,10 @@ impl Parameters {
         Ok(self)
     }
 
+    /// Enable or disable autocommit mode for new connections. When it is
+    /// disabled, the connection checks after logging in that the server has
+    /// actually left autocommit mode and fails with
+    /// [`ConnectError::Rejected`](`crate::ConnectError::Rejected`) otherwise.
     pub fn set_autocommit(&mut self, value: bool) -> ParmResult<()> {
         self.set(Parm::Autocommit, value)
     }
@@ -775,6 +1271,9 @@ impl Parameters {
         Ok(self)
     }
 
+    /// Set the number of rows the server includes in the initial reply to a
+    /// query, the rest is fetched in chunks of the same size. Zero or a
+    /// negative value means all rows are sent at once.
     pub fn set_replysize(&mut self, value: impl Into<i64>) -> ParmResult<()> {
         self.set(Parm::ReplySize, value.into())
     }
@@ -793,6 +1292,38 @@ impl Parameters {
         Ok(self)
     }
 
+    /// Execute the given SQL statement on every new connection, right after
+    /// logging in and setting the [schema](`Parameters::set_schema`).
+    /// Statements are executed in the order they were added. If one of them
+    /// fails, the connection fails with
+    /// [`ConnectError::Rejected`](`crate::ConnectError::Rejected`).
+    ///
+    /// Useful for session settings that have no parameter of their own, for
+    /// example `SET optimizer = 'minimal_pipe'`. Init statements are not part
+    /// of the URL, are left out when serializing with serde and are not
+    /// compared by [`diff()`](`Parameters::diff`). They have to be added again
+    /// after a round trip through either.
+    pub fn add_init_statement(&mut self, sql: &str) {
+        self.init_statements.push(sql.to_string());
+    }
+
+    pub fn with_init_statement(mut self, sql: &str) -> Parameters {
+        self.add_init_statement(sql);
+        self
+    }
+
+    /// Return the statements added with
+    /// [`add_init_statement()`](`Parameters::add_init_statement`).
+    pub fn init_statements(&self) -> &[String] {
+        &self.init_statements
+    }
+
+    /// Remove all statements added with
+    /// [`add_init_statement()`](`Parameters::add_init_statement`).
+    pub fn clear_init_statements(&mut self) {
+        self.init_statements.clear();
+    }
+
     pub fn set_sock(&mut self, value: &str) -> ParmResult<()> {
         self.set(Parm::Sock, value)
     }
@@ -820,6 +1351,27 @@ impl Parameters {
         Ok(self)
     }
 
+    /// Set the time zone by name rather than as a number of minutes east of
+    /// UTC. The name is either `local` for the time zone of this machine or,
+    /// with the `tz-rs` feature, the name of a zone in the time zone database
+    /// of the operating system, for example `Europe/Amsterdam`. The name is
+    /// resolved to an offset when the parameters are validated, which happens
+    /// when connecting. Other names fail validation.
+    ///
+    /// `local` resolves to the same offset that is sent when no time zone
+    /// has been set at all, which is UTC unless the `time` feature is
+    /// enabled.
+    ///
+    /// This can also be written as `timezone=Europe/Amsterdam` in a URL.
+    pub fn set_timezone_name(&mut self, value: &str) -> ParmResult<()> {
+        self.set(Parm::Timezone, value)
+    }
+
+    pub fn with_timezone_name(mut self, value: &str) -> ParmResult<Parameters> {
+        self.set_timezone_name(value)?;
+        Ok(self)
+    }
+
     pub fn set_connect_timeout(&mut self, value: impl Into<i64>) -> ParmResult<()> {
         self.set(Parm::ConnectTimeout, value.into())
     }
@@ -829,6 +1381,75 @@ impl Parameters {
         Ok(self)
     }
 
+    /// Ask the server to abort queries that run longer than the given number
+    /// of milliseconds. The query then fails with a [`CursorError::Server`][`crate::CursorError::Server`].
+    ///
+    /// Unlike [`connect_timeout`][`Parameters::set_connect_timeout`], which
+    /// is enforced by the client and only while establishing the connection,
+    /// this timeout is enforced by the server for every query on the
+    /// connection. The server works in whole seconds so the value is rounded
+    /// up.
+    pub fn set_query_timeout(&mut self, value: impl Into<i64>) -> ParmResult<()> {
+        self.set(Parm::QueryTimeout, value.into())
+    }
+
+    pub fn with_query_timeout(mut self, value: impl Into<i64>) -> ParmResult<Parameters> {
+        self.set_query_timeout(value)?;
+        Ok(self)
+    }
+
+    /// Log a warning with the SQL text when executing statements takes
+    /// longer than the given number of milliseconds. Long string literals
+    /// are redacted from the logged SQL.
+    pub fn set_slow_query_log_ms(&mut self, value: impl Into<i64>) -> ParmResult<()> {
+        self.set(Parm::SlowQueryLogMs, value.into())
+    }
+
+    pub fn with_slow_query_log_ms(mut self, value: impl Into<i64>) -> ParmResult<Parameters> {
+        self.set_slow_query_log_ms(value)?;
+        Ok(self)
+    }
+
+    /// Refuse to hold more than this many bytes of a single reply in memory.
+    /// Zero or negative means no limit.
+    ///
+    /// If the reply to a query, or a batch of rows fetched later, would exceed
+    /// the limit, the rest of it is skipped without being stored and the
+    /// operation fails with
+    /// [`CursorError::ResultTooLarge`][`crate::CursorError::ResultTooLarge`].
+    /// The connection remains usable. While more rows are being fetched, the
+    /// rows of the initial reply are kept and count towards the limit.
+    ///
+    /// The limit is checked against what the server sends, so it should be
+    /// large enough for [`replysize`][`Parameters::set_replysize`] rows. With
+    /// a replysize of zero the whole result set is sent at once and the limit
+    /// applies to all of it. Use
+    /// [`maxprefetch`][`Parameters::set_maxprefetch`] to bound the batches
+    /// fetched after the initial reply.
+    pub fn set_max_result_bytes(&mut self, value: impl Into<i64>) -> ParmResult<()> {
+        self.set(Parm::MaxResultBytes, value.into())
+    }
+
+    pub fn with_max_result_bytes(mut self, value: impl Into<i64>) -> ParmResult<Parameters> {
+        self.set_max_result_bytes(value)?;
+        Ok(self)
+    }
+
+    /// Fetch at most this many rows at a time when more rows of a result set
+    /// are needed, even if [`replysize`][`Parameters::set_replysize`] is
+    /// larger. This bounds how far the driver reads ahead of the rows
+    /// actually consumed. The rows included in the initial reply to a query
+    /// are still governed by `replysize` alone. Zero or negative means no
+    /// bound.
+    pub fn set_maxprefetch(&mut self, value: impl Into<i64>) -> ParmResult<()> {
+        self.set(Parm::MaxPrefetch, value.into())
+    }
+
+    pub fn with_maxprefetch(mut self, value: impl Into<i64>) -> ParmResult<Parameters> {
+        self.set_maxprefetch(value)?;
+        Ok(self)
+    }
+
     pub fn set_client_info(&mut self, value: &str) -> ParmResult<()> {
         self.set(Parm::ClientInfo, value)
     }
@@ -855,6 +1476,85 @@ impl Parameters {
         self.set_client_remark(value)?;
         Ok(self)
     }
+
+    /// Whether to try a Unix Domain socket when no host or `localhost` has
+    /// been given. Defaults to `true`. Set it to `false` to always connect
+    /// over TCP. A socket given explicitly with
+    /// [`sock`][`Parameters::set_sock`] is still used.
+    pub fn set_prefer_unix(&mut self, value: bool) -> ParmResult<()> {
+        self.set(Parm::PreferUnix, value)
+    }
+
+    pub fn with_prefer_unix(mut self, value: bool) -> ParmResult<Parameters> {
+        self.set_prefer_unix(value)?;
+        Ok(self)
+    }
+
+    /// Whether [`Connection::new()`][`crate::Connection::new`] should retrieve
+    /// the [server metadata][`crate::Connection::metadata`] right away
+    /// rather than when it is first needed. Defaults to `false`. Setting it
+    /// makes connecting slower but saves a round trip later, for example
+    /// before the first query in latency sensitive code.
+    pub fn set_fetch_metadata_on_connect(&mut self, value: bool) -> ParmResult<()> {
+        self.set(Parm::FetchMetadataOnConnect, value)
+    }
+
+    pub fn with_fetch_metadata_on_connect(mut self, value: bool) -> ParmResult<Parameters> {
+        self.set_fetch_metadata_on_connect(value)?;
+        Ok(self)
+    }
+
+    /// Comma separated list of handshake options not to send, for example
+    /// `time_zone,reply_size`. The corresponding settings are then applied
+    /// using separate commands after the login, as is done for servers that
+    /// do not support handshake options at all. Useful to work around servers
+    /// that mishandle a particular option. The options are `auto_commit`,
+    /// `reply_size`, `size_header` and `time_zone`. Defaults to empty.
+    pub fn set_suppress_handshake_options(&mut self, value: &str) -> ParmResult<()> {
+        self.set(Parm::SuppressHandshakeOptions, value)
+    }
+
+    pub fn with_suppress_handshake_options(mut self, value: &str) -> ParmResult<Parameters> {
+        self.set_suppress_handshake_options(value)?;
+        Ok(self)
+    }
+
+    /// Override the library name sent to the server as part of the client
+    /// info. If left empty, the name and version of this crate are sent.
+    pub fn set_client_library(&mut self, value: &str) -> ParmResult<()> {
+        self.set(Parm::ClientLibrary, value)
+    }
+
+    pub fn with_client_library(mut self, value: &str) -> ParmResult<Parameters> {
+        self.set_client_library(value)?;
+        Ok(self)
+    }
+
+    /// Set the hash algorithms to prefer when hashing the password, for
+    /// example `SHA512` or `SHA512,SHA384`. The first one the server also
+    /// supports is used. If none of them is offered, the algorithm is chosen
+    /// in the order preferred by the server.
+    pub fn set_hash(&mut self, value: &str) -> ParmResult<()> {
+        self.set(Parm::Hash, value)
+    }
+
+    pub fn with_hash(mut self, value: &str) -> ParmResult<Parameters> {
+        self.set_hash(value)?;
+        Ok(self)
+    }
+
+    /// Announce the given byte order, `BIG` or `LIT`, to the server instead
+    /// of the native one. Only useful to test cross-endian behavior.
+    #[doc(hidden)]
+    pub fn set_client_endian(&mut self, value: &str) -> ParmResult<()> {
+        self.set(Parm::ClientEndian, value)
+    }
+
+    #[doc(hidden)]
+    pub fn with_client_endian(mut self, value: &str) -> ParmResult<Parameters> {
+        self.set_client_endian(value)?;
+        Ok(self)
+    }
 }
 
 /// Indicates how the TLS certificate of the server must be verified.
@@ -878,7 +1578,7 @@ pub enum TlsVerify {
 /// For example, based on the combination of `host`, `port`, `database` and
 /// `sock` it knows whether a connection must be made to a Unix Domain socket, a
 /// TCP socket or both.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Validated<'a> {
     pub database: Cow<'a, str>,
     pub tls: bool,
@@ -888,13 +1588,27 @@ pub struct Validated<'a> {
     pub cert: Cow<'a, str>,
     pub language: Cow<'a, str>,
     pub replysize: usize,
+    /// Upper bound on the number of rows fetched at once after the initial
+    /// reply, `None` if only `replysize` applies.
+    pub maxprefetch: Option<usize>,
     pub schema: Cow<'a, str>,
     pub client_info: bool,
     pub client_application: Cow<'a, str>,
     pub client_remark: Cow<'a, str>,
+    pub client_library: Cow<'a, str>,
+    pub fetch_metadata_on_connect: bool,
+    /// Handshake options to send as separate commands instead, by the name
+    /// they have in the handshake.
+    pub suppress_handshake_options: Vec<&'static str>,
+    pub hash: Cow<'a, str>,
     pub connect_timezone_seconds: Option<i32>,
     pub connect_scan: bool,
+    /// Path of the Unix Domain socket to try first, or empty if no Unix
+    /// Domain connection should be attempted.
     pub connect_unix: Cow<'a, str>,
+    /// Path to try if nothing is found at `connect_unix`. Set to the socket in
+    /// `$XDG_RUNTIME_DIR` if `sockdir` has been left at its default.
+    pub connect_unix_fallback: Option<String>,
     pub connect_tcp: Cow<'a, str>,
     pub connect_port: u16,
     pub connect_tls_verify: TlsVerify,
@@ -903,11 +1617,18 @@ pub struct Validated<'a> {
     pub connect_clientcert: Cow<'a, str>,
     pub connect_binary: u16,
     pub connect_timeout: Option<Duration>,
+    pub query_timeout: Option<Duration>,
+    pub slow_query_log: Option<Duration>,
+    /// Statements to execute after logging in.
+    pub init_statements: &'a [String],
+    /// Maximum size of a reply held in memory, `None` if unlimited.
+    pub max_result_bytes: Option<usize>,
+    pub client_endian: Endian,
 }
 
 impl Validated<'_> {
     #[allow(unused_variables)]
-    fn new(parms: &Parameters) -> ParmResult<Validated> {
+    fn new(parms: &Parameters) -> ParmResult<Validated<'_>> {
         use Parm::*;
         use ParmError::*;
 
@@ -929,13 +1650,23 @@ impl Validated<'_> {
         let raw_sock: Cow<str> = parms.get_str(Sock)?;
         let raw_sockdir: Cow<str> = parms.get_str(SockDir)?;
 
-        let raw_timezone: i64 = parms.get_int(Timezone)?;
+        let raw_timezone: &Value = parms.get(Timezone);
         let raw_binary: &Value = parms.get(Binary);
         let raw_connect_timeout: Option<i64> = parms.get(ConnectTimeout).int_value();
+        let raw_query_timeout: Option<i64> = parms.get(QueryTimeout).int_value();
+        let raw_slow_query_log_ms: Option<i64> = parms.get(SlowQueryLogMs).int_value();
+        let raw_maxprefetch: Option<i64> = parms.get(MaxPrefetch).int_value();
+        let raw_max_result_bytes: Option<i64> = parms.get(MaxResultBytes).int_value();
 
         let raw_client_info = parms.get_bool(ClientInfo)?;
         let raw_client_application = parms.get_str(ClientApplication)?;
         let raw_client_remark = parms.get_str(ClientRemark)?;
+        let raw_client_library = parms.get_str(ClientLibrary)?;
+        let raw_prefer_unix = parms.get_bool(PreferUnix)?;
+        let raw_fetch_metadata_on_connect = parms.get_bool(FetchMetadataOnConnect)?;
+        let raw_suppress_handshake_options = parms.get_str(SuppressHandshakeOptions)?;
+        let raw_hash: Cow<str> = parms.get_str(Hash)?;
+        let raw_client_endian: Cow<str> = parms.get_str(ClientEndian)?;
 
         let raw_tableschema: Cow<str> = parms.get_str(TableSchema)?;
         let raw_table: Cow<str> = parms.get_str(Table)?;
@@ -1002,17 +1733,39 @@ impl Validated<'_> {
         }
 
         // Specific to this crate
+
+        // The user name is sent as a field of the colon separated login
+        // response, the schema in a SET SCHEMA command. The database name has
+        // already been checked above and the password is only sent hashed so
+        // it may contain anything.
+        for (parm, value, colon_allowed) in [(User, &raw_user, false), (Schema, &raw_schema, true)]
+        {
+            let forbidden = |c: char| c.is_control() || (c == ':' && !colon_allowed);
+            if let Some(c) = value.chars().find(|&c| forbidden(c)) {
+                return Err(ForbiddenCharacter(parm, c));
+            }
+        }
+
+        // The server treats the statements as NUL terminated strings
+        if let Some(idx) = parms.init_statements.iter().position(|s| s.contains('\0')) {
+            return Err(InvalidInitStatement(idx));
+        }
+
         if raw_client_info && raw_client_application.contains('\n') {
             return Err(ClientInfoNewline(ClientApplication));
         }
         if raw_client_info && raw_client_remark.contains('\n') {
             return Err(ClientInfoNewline(ClientRemark));
         }
+        if raw_client_info && raw_client_library.contains('\n') {
+            return Err(ClientInfoNewline(ClientLibrary));
+        }
         // Virtual parameters
 
         // connect_port and connect_binary have already been determined above
 
         let connect_scan = !database.is_empty()
+            && raw_prefer_unix
             && raw_sock.is_empty()
             && raw_host.is_empty()
             && raw_port == -1
@@ -1023,7 +1776,7 @@ impl Validated<'_> {
 
         let connect_unix = if !sock_empty {
             raw_sock
-        } else if raw_tls {
+        } else if raw_tls || !raw_prefer_unix {
             "".into()
         } else if host_empty {
             format!("{dir}/.s.monetdb.{connect_port}", dir = raw_sockdir).into()
@@ -1031,6 +1784,13 @@ impl Validated<'_> {
             "".into()
         };
 
+        let connect_unix_fallback = match std::env::var("XDG_RUNTIME_DIR") {
+            Ok(dir) if !connect_unix.is_empty() && sock_empty && parms.is_default(SockDir) => {
+                Self::unix_fallback(&dir, &connect_unix, connect_port)
+            }
+            _ => None,
+        };
+
         let connect_tcp = if !sock_empty {
             "".into()
         } else if host_empty {
@@ -1057,7 +1817,7 @@ impl Validated<'_> {
         };
 
         let connect_timezone_seconds = if parms.timezone_set {
-            Some(raw_timezone as i32 * 60)
+            Some(Self::timezone_seconds(raw_timezone)?)
         } else {
             None
         };
@@ -1067,7 +1827,50 @@ impl Validated<'_> {
             _ => None,
         };
 
-        let Ok(replysize) = raw_replysize.try_into() else {
+        let query_timeout = match raw_query_timeout {
+            Some(i @ 1..) => Some(Duration::from_millis(i as u64)),
+            _ => None,
+        };
+
+        let slow_query_log = match raw_slow_query_log_ms {
+            Some(i @ 1..) => Some(Duration::from_millis(i as u64)),
+            _ => None,
+        };
+
+        let mut suppress_handshake_options = vec![];
+        for name in raw_suppress_handshake_options.split(',').map(str::trim) {
+            let option = match name {
+                "" => continue,
+                "auto_commit" | "autocommit" => "auto_commit",
+                "reply_size" | "replysize" => "reply_size",
+                "size_header" => "size_header",
+                "time_zone" | "timezone" => "time_zone",
+                _ => return Err(InvalidValue(SuppressHandshakeOptions)),
+            };
+            suppress_handshake_options.push(option);
+        }
+
+        // zero or negative means no limit
+        let maxprefetch = match raw_maxprefetch {
+            Some(i @ 1..) => Some(usize::try_from(i).map_err(|_| InvalidInt(MaxPrefetch))?),
+            _ => None,
+        };
+
+        // zero or negative means no limit
+        let max_result_bytes = match raw_max_result_bytes {
+            Some(i @ 1..) => Some(usize::try_from(i).map_err(|_| InvalidInt(MaxResultBytes))?),
+            _ => None,
+        };
+
+        let client_endian = match raw_client_endian.to_ascii_uppercase().as_str() {
+            "" => Endian::NATIVE,
+            "BIG" => Endian::Big,
+            "LIT" => Endian::Lit,
+            _ => return Err(InvalidValue(ClientEndian)),
+        };
+
+        // zero or negative means no limit
+        let Ok(replysize) = raw_replysize.max(0).try_into() else {
             return Err(ParmError::InvalidInt(Parm::ReplySize));
         };
 
@@ -1082,13 +1885,24 @@ impl Validated<'_> {
             cert: raw_cert,
             language: raw_language,
             replysize,
+            maxprefetch,
             schema: raw_schema,
             connect_timeout,
+            query_timeout,
+            slow_query_log,
+            init_statements: &parms.init_statements,
+            max_result_bytes,
+            client_endian,
             client_info: raw_client_info,
             client_application: raw_client_application,
             client_remark: raw_client_remark,
+            client_library: raw_client_library,
+            fetch_metadata_on_connect: raw_fetch_metadata_on_connect,
+            suppress_handshake_options,
+            hash: raw_hash,
             connect_scan,
             connect_unix,
+            connect_unix_fallback,
             connect_tcp,
             connect_port,
             connect_tls_verify,
@@ -1102,6 +1916,13 @@ impl Validated<'_> {
         Ok(validated)
     }
 
+    /// The socket to try if `connect_unix`, derived from the default sockdir,
+    /// does not exist. `runtime_dir` is normally `$XDG_RUNTIME_DIR`.
+    fn unix_fallback(runtime_dir: &str, connect_unix: &str, port: u16) -> Option<String> {
+        let path = format!("{runtime_dir}/.s.monetdb.{port}");
+        (!runtime_dir.is_empty() && path != connect_unix).then_some(path)
+    }
+
     fn valid_name<T: AsRef<str>>(parm: Parm, name: T) -> ParmResult<T> {
         let the_error = Err(ParmError::InvalidValue(parm));
 
@@ -1133,17 +1954,130 @@ impl Validated<'_> {
         }
         Ok(digits)
     }
+
+    /// Interpret the timezone parameter: a number of minutes east of UTC,
+    /// `local` or the name of a zone such as `Europe/Amsterdam`.
+    fn timezone_seconds(value: &Value) -> ParmResult<i32> {
+        if let Some(minutes) = value.int_value() {
+            return Ok(minutes as i32 * 60);
+        }
+        let name = value.str_value();
+        let seconds = if name == "local" {
+            Some(local_timezone_seconds())
+        } else {
+            Self::zone_seconds(&name)
+        };
+        seconds.ok_or(ParmError::InvalidValue(Parm::Timezone))
+    }
+
+    /// Look up the current offset of the named zone in the time zone database
+    /// of the operating system.
+    #[cfg(feature = "tz-rs")]
+    fn zone_seconds(name: &str) -> Option<i32> {
+        // only accept zone names, not paths to arbitrary files
+        if name.starts_with(['/', ':'])
+            || name
+                .split('/')
+                .any(|p| p.is_empty() || p == "." || p == "..")
+        {
+            return None;
+        }
+        let zone = tz::TimeZone::from_posix_tz(name).ok()?;
+        let local_time_type = zone.find_current_local_time_type().ok()?;
+        Some(local_time_type.ut_offset())
+    }
+
+    /// Zone names are only supported with the `tz-rs` feature.
+    #[cfg(not(feature = "tz-rs"))]
+    fn zone_seconds(_name: &str) -> Option<i32> {
+        None
+    }
 }
 
 impl Parameters {
     /// Convert the Parameters into a URL including user name and password.
+    /// [Init statements](`Parameters::add_init_statement`) are not included.
     pub fn url_with_credentials(&self) -> ParmResult<String> {
         url_from_parms(self, Parm::iter())
     }
 
     /// Convert the Parameters into a URL not including user name and password.
+    /// [Init statements](`Parameters::add_init_statement`) are not included.
     pub fn url_without_credentials(&self) -> ParmResult<String> {
         let selection = Parm::iter().filter(|p| !p.is_sensitive());
         url_from_parms(self, selection)
     }
 }
+
+/// Same as [`Parameters::from_url`].
+impl TryFrom<&str> for Parameters {
+    type Error = ParmError;
+
+    fn try_from(url: &str) -> ParmResult<Parameters> {
+        Parameters::from_url(url)
+    }
+}
+
+/// Same as [`Parameters::url_with_credentials`].
+impl TryFrom<&Parameters> for String {
+    type Error = ParmError;
+
+    fn try_from(parms: &Parameters) -> ParmResult<String> {
+        parms.url_with_credentials()
+    }
+}
+
+/// Same as [`Parameters::url_with_credentials`].
+impl TryFrom<Parameters> for String {
+    type Error = ParmError;
+
+    fn try_from(parms: Parameters) -> ParmResult<String> {
+        parms.url_with_credentials()
+    }
+}
+
+#[test]
+fn test_url_roundtrip() {
+    // Small deterministic pseudo random generator, xorshift64
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+
+    let strings = [
+        "", "demo", "sys", "/tmp", "a b", "x&y=z", "50%#off", "1+1/2?", "[\u{e9}]",
+    ];
+    let hosts = ["", "localhost", "example.com", "127.0.0.1", "::1"];
+    let ints = [-1, 0, 1, 60, 12345, 50000];
+
+    for _ in 0..2000 {
+        let mut parms = Parameters::default();
+        for parm in Parm::iter() {
+            // leave most parameters alone so the result is often valid
+            if random(6) != 0 {
+                continue;
+            }
+            let value: Value = match (parm, parm.parm_type()) {
+                (Parm::Host, _) => hosts[random(hosts.len())].into(),
+                (_, ParmType::Bool) => Value::Bool(random(2) == 0),
+                (_, ParmType::Int) => Value::Int(ints[random(ints.len())]),
+                (_, ParmType::Str) => strings[random(strings.len())].into(),
+            };
+            parms.set(parm, value).unwrap();
+        }
+        parms.boundary();
+
+        let url = String::try_from(&parms).unwrap();
+        let Ok(reparsed) = Parameters::try_from(url.as_str()) else {
+            panic!("cannot parse {url:?} rendered from {parms:?}");
+        };
+        assert_eq!(
+            reparsed.validate(),
+            parms.validate(),
+            "url {url:?} rendered from {parms:?}"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+//! Serde support for [`Parameters`], enabled by the `serde` feature.
+
+use std::fmt;
+
+use serde::{
+    de::{self, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{Parameters, Parm, Value};
+
+/// Wrapper returned by [`Parameters::serializable()`] to control whether the
+/// password is included in the serialized form.
+#[derive(Debug, Clone, Copy)]
+pub struct SerializableParameters<'a> {
+    parms: &'a Parameters,
+    include_password: bool,
+}
+
+impl Parameters {
+    /// Return a wrapper that serializes these parameters, including the
+    /// password only if `include_password` is true.
+    ///
+    /// Serializing the Parameters directly leaves out the password.
+    pub fn serializable(&self, include_password: bool) -> SerializableParameters<'_> {
+        SerializableParameters {
+            parms: self,
+            include_password,
+        }
+    }
+}
+
+/// Serialized as a map from parameter name to value. Parameters that have
+/// their default value are left out, and so is the password, see
+/// [`Parameters::serializable()`]. The
+/// [init statements](`Parameters::add_init_statement`) are not serialized.
+impl Serialize for Parameters {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serializable(false).serialize(serializer)
+    }
+}
+
+impl Serialize for SerializableParameters<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let parms = self
+            .parms
+            .explicit_parms()
+            .filter(|&p| self.include_password || p != Parm::Password);
+        let mut map = serializer.serialize_map(None)?;
+        for parm in parms {
+            map.serialize_entry(parm.as_str(), self.parms.get(parm))?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Str(s) => serializer.serialize_str(s),
+        }
+    }
+}
+
+/// Deserialized from a map from parameter name to value. Each entry is applied
+/// using [`Parameters::set_named()`] so invalid names and values are reported
+/// as errors, and unknown names containing an underscore are ignored.
+impl<'de> Deserialize<'de> for Parameters {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ParametersVisitor)
+    }
+}
+
+struct ParametersVisitor;
+
+impl<'de> Visitor<'de> for ParametersVisitor {
+    type Value = Parameters;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of MonetDB connection parameters")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Parameters, A::Error> {
+        let mut parms = Parameters::default();
+        while let Some((name, value)) = access.next_entry::<String, Value>()? {
+            parms.set_named(&name, value).map_err(de::Error::custom)?;
+        }
+        parms.boundary();
+        Ok(parms)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl Visitor<'_> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, integer or boolean")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        match i64::try_from(v) {
+            Ok(i) => Ok(Value::Int(i)),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+}
+
+#[test]
+fn test_serde_roundtrip() {
+    let parms = Parameters::from_url("monetdb://db.example.com:12345/demo?user=me&password=secret")
+        .unwrap()
+        .with_autocommit(false)
+        .unwrap()
+        .with_init_statement("SET ROLE analyst");
+
+    let json = serde_json::to_value(&parms).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "host": "db.example.com",
+            "port": 12345,
+            "database": "demo",
+            "user": "me",
+            "autocommit": false,
+        })
+    );
+
+    let json = serde_json::to_string(&parms.serializable(true)).unwrap();
+    assert!(json.contains(r#""password":"secret""#));
+    let back: Parameters = serde_json::from_str(&json).unwrap();
+    assert!(back.diff(&parms).is_empty());
+    assert!(back.init_statements().is_empty());
+}
+
+#[test]
+fn test_serde_validation() {
+    let parse = |json: &str| serde_json::from_str::<Parameters>(json);
+
+    let parms = parse(r#"{"database": "demo", "replysize": "500", "tls": "on"}"#).unwrap();
+    assert_eq!(parms.get_str(Parm::Database).unwrap(), "demo");
+    assert_eq!(parms.get_int(Parm::ReplySize).unwrap(), 500);
+    assert!(parms.get_bool(Parm::Tls).unwrap());
+
+    // unknown names with an underscore are ignored, without are rejected
+    assert!(parse(r#"{"some_thing": 42}"#).is_ok());
+    let err = parse(r#"{"frobnicate": 42}"#).unwrap_err();
+    assert!(err.to_string().contains("unknown parameter 'frobnicate'"));
+
+    // invalid values are rejected
+    let err = parse(r#"{"autocommit": 42}"#).unwrap_err();
+    assert!(err.to_string().contains("autocommit"), "{err}");
+    assert!(parse(r#"{"port": [1, 2]}"#).is_err());
+}
@@ -128,16 +128,17 @@ fn percent_decode(s: &str) -> ParmResult<Cow<'_, str>> {
     }
 }
 
+/// Percent-encode a host name, path component or query parameter value.
+/// Characters that delimit any of these, such as `/`, `&` and `#`, are always
+/// encoded, and so is `+` because it means space in a query string.
 fn percent_encode(buffer: &mut String, s: &str) {
     for &byte in s.as_bytes() {
         let safe = matches!(
             byte,
             b'a' ..= b'z' | b'A' ..= b'Z' | b'0' ..= b'9'
             | b'-' | b'.' | b'_' | b'~' | b'!'
-            | b'#' | b'$' | b'&' | b'\'' | b'('
-            | b')' | b'*' | b'+' | b',' | b'/'
-            | b':' | b';' | b'=' | b'?' | b'@'
-            | b'[' | b']'
+            | b'$' | b'\'' | b'(' | b')' | b'*'
+            | b',' | b':' | b';' | b'@'
         );
         if safe {
             buffer.push(byte as char);
@@ -252,19 +253,26 @@ pub fn url_from_parms(
     url.push_str(scheme);
     url.push_str("://");
 
+    // An explicit 50000 is not the same as -1, it disables connect_scan
     let port = match parms.get_int(Port)? {
-        -1 | 50000 => None,
+        -1 => None,
         p => Some(p),
     };
     let host = parms.get_str(Host)?;
     let mut host: &str = &host;
+    // The parser maps localhost to the empty host and localhost. to localhost
     if host == "localhost" {
-        host = "";
+        host = "localhost.";
     }
     if port.is_some() && host.is_empty() {
         host = "localhost";
     }
-    percent_encode(&mut url, host);
+    if host.contains(':') {
+        // IPv6 address
+        write!(url, "[{host}]").unwrap();
+    } else {
+        percent_encode(&mut url, host);
+    }
     if let Some(p) = port {
         write!(url, ":{p}").unwrap();
     }
@@ -290,7 +298,7 @@ pub fn url_from_parms(
         if p.is_core() {
             continue;
         }
-        if !parms.is_default(p) {
+        if parms.is_explicit(p) {
             url.push(sep);
             url.push_str(p.as_str());
             url.push('=');
@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+//! Helpers for safely putting Rust values into SQL text.
+
+use std::fmt;
+
+use crate::parms::Value;
+
+/// Types that can be rendered as a MonetDB SQL literal.
+///
+/// Strings are rendered as raw strings, `R'...'`, so only the single quote
+/// needs escaping. `None` is rendered as `NULL`.
+pub trait ToMonetSql {
+    /// Write the SQL literal for this value to `out`.
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result;
+}
+
+/// Wrapper whose [`Display`](`fmt::Display`) implementation renders the
+/// value as a SQL literal using [`ToMonetSql`].
+pub struct SqlLiteral<'a, T: ?Sized>(pub &'a T);
+
+impl<T: ToMonetSql + ?Sized> fmt::Display for SqlLiteral<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_sql(f)
+    }
+}
+
+/// Render the value as a SQL literal.
+///
+/// Fails for values that have no SQL literal, such as floating point infinity
+/// and NaN.
+pub fn literal<T: ToMonetSql + ?Sized>(value: &T) -> Result<String, fmt::Error> {
+    let mut out = String::new();
+    value.write_sql(&mut out)?;
+    Ok(out)
+}
+
+/// Render the values as a parenthesized, comma-separated list of SQL
+/// literals for use with `IN`, for example `(1, 2, 3)` or `(R'a', R'b')`.
+///
+/// SQL does not allow an empty list, so an empty slice is rendered as
+/// `(NULL)`. Nothing is equal to NULL, so `x IN (NULL)` selects no rows,
+/// but note that `x NOT IN (NULL)` does not select any rows either.
+///
+/// Like [`literal()`], this fails if any of the values has no SQL literal.
+///
+/// ```
+/// # use monetdb::sqlgen::in_list;
+/// let names = ["Alice", "O'Brien"];
+/// let sql = format!("SELECT * FROM people WHERE name IN {}", in_list(&names)?);
+/// assert_eq!(sql, "SELECT * FROM people WHERE name IN (R'Alice', R'O''Brien')");
+/// # Ok::<(), std::fmt::Error>(())
+/// ```
+pub fn in_list<T: ToMonetSql>(values: &[T]) -> Result<String, fmt::Error> {
+    if values.is_empty() {
+        return Ok("(NULL)".to_string());
+    }
+    let mut out = String::from("(");
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        value.write_sql(&mut out)?;
+    }
+    out.push(')');
+    Ok(out)
+}
+
+impl<T: ToMonetSql + ?Sized> ToMonetSql for &T {
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        (**self).write_sql(out)
+    }
+}
+
+impl<T: ToMonetSql> ToMonetSql for Option<T> {
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self {
+            Some(value) => value.write_sql(out),
+            None => out.write_str("NULL"),
+        }
+    }
+}
+
+impl ToMonetSql for str {
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        out.write_str("R'")?;
+        for (i, part) in self.split('\'').enumerate() {
+            if i > 0 {
+                out.write_str("''")?;
+            }
+            out.write_str(part)?;
+        }
+        out.write_str("'")
+    }
+}
+
+impl ToMonetSql for String {
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.as_str().write_sql(out)
+    }
+}
+
+impl ToMonetSql for bool {
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        out.write_str(if *self { "true" } else { "false" })
+    }
+}
+
+macro_rules! int_tomonetsql {
+    ($($type:ty),*) => {
+        $(
+            impl ToMonetSql for $type {
+                fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+                    write!(out, "{self}")
+                }
+            }
+        )*
+    };
+}
+
+int_tomonetsql!(i8, u8, i16, u16, i32, u32, i64, u64, i128, isize, usize);
+
+macro_rules! float_tomonetsql {
+    ($($type:ty),*) => {
+        $(
+            /// MonetDB has no literals for infinity and NaN, trying to
+            /// render them fails with a formatter error.
+            impl ToMonetSql for $type {
+                fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+                    if !self.is_finite() {
+                        return Err(fmt::Error);
+                    }
+                    write!(out, "{self:?}")
+                }
+            }
+        )*
+    };
+}
+
+float_tomonetsql!(f32, f64);
+
+impl ToMonetSql for Value {
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self {
+            Value::Bool(b) => b.write_sql(out),
+            Value::Int(i) => i.write_sql(out),
+            Value::Str(s) => s.write_sql(out),
+        }
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl ToMonetSql for uuid::Uuid {
+    fn write_sql(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        write!(out, "UUID '{}'", self.hyphenated())
+    }
+}
+
+/// Prepare SQL text for logging: replace string literals longer than a few
+/// characters with `'***'` and truncate the result.
+pub(crate) fn redact_for_log(sql: &str) -> String {
+    const MAX_LITERAL: usize = 16;
+    const MAX_LEN: usize = 200;
+
+    let mut out = String::with_capacity(sql.len().min(MAX_LEN + 3));
+    let mut rest = sql;
+    while let Some(start) = rest.find('\'') {
+        let raw = rest[..start].ends_with(['r', 'R']);
+        out.push_str(&rest[..=start]);
+        rest = &rest[start + 1..];
+
+        // find the closing quote, skipping '' and, in non-raw strings,
+        // backslash escapes
+        let mut end = None;
+        let mut chars = rest.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' if !raw => {
+                    chars.next();
+                }
+                '\'' if matches!(chars.peek(), Some((_, '\''))) => {
+                    chars.next();
+                }
+                '\'' => {
+                    end = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let end = end.unwrap_or(rest.len());
+        let literal = &rest[..end];
+        if literal.chars().count() > MAX_LITERAL {
+            out.push_str("***");
+        } else {
+            out.push_str(literal);
+        }
+        rest = &rest[end..];
+        if let Some(r) = rest.strip_prefix('\'') {
+            out.push('\'');
+            rest = r;
+        }
+    }
+    out.push_str(rest);
+
+    if let Some((cut, _)) = out.char_indices().nth(MAX_LEN) {
+        out.truncate(cut);
+        out.push_str("...");
+    }
+    out
+}
+
+#[test]
+fn test_redact_for_log() {
+    assert_eq!(redact_for_log("SELECT 'short'"), "SELECT 'short'");
+    assert_eq!(
+        redact_for_log("SELECT 'a rather long secret value', 42"),
+        "SELECT '***', 42"
+    );
+    assert_eq!(
+        redact_for_log("SELECT 'it''s a rather long secret', 'x'"),
+        "SELECT '***', 'x'"
+    );
+    assert_eq!(
+        redact_for_log(r"SELECT 'esc\' is still a long secret', 'x'"),
+        "SELECT '***', 'x'"
+    );
+    assert_eq!(redact_for_log(r"SELECT R'a', 'x'"), r"SELECT R'a', 'x'");
+    assert_eq!(
+        redact_for_log("SELECT 'unterminated and rather long"),
+        "SELECT '***"
+    );
+
+    let long = format!("SELECT {}", "1 + ".repeat(100));
+    let redacted = redact_for_log(&long);
+    assert_eq!(redacted.len(), 203);
+    assert!(redacted.ends_with("..."));
+}
+
+#[test]
+fn test_literals() {
+    assert_eq!(literal("foo").unwrap(), "R'foo'");
+    assert_eq!(literal("it's").unwrap(), "R'it''s'");
+    assert_eq!(literal(r"a\b").unwrap(), r"R'a\b'");
+    assert_eq!(literal("").unwrap(), "R''");
+    assert_eq!(literal(&42i32).unwrap(), "42");
+    assert_eq!(literal(&-1i64).unwrap(), "-1");
+    assert_eq!(literal(&true).unwrap(), "true");
+    assert_eq!(literal(&1.5f64).unwrap(), "1.5");
+    assert_eq!(literal(&1e300f64).unwrap(), "1e300");
+    assert_eq!(literal(&None::<i32>).unwrap(), "NULL");
+    assert_eq!(literal(&Some("x")).unwrap(), "R'x'");
+    assert_eq!(literal(&Value::from("y'z")).unwrap(), "R'y''z'");
+    assert_eq!(literal(&Value::Int(7)).unwrap(), "7");
+
+    #[cfg(feature = "uuid")]
+    assert_eq!(
+        literal(&uuid::Uuid::from_u128(
+            0x7b4dcdd0_e0f2_4d05_a81b_599f445843b6
+        ))
+        .unwrap(),
+        "UUID '7b4dcdd0-e0f2-4d05-a81b-599f445843b6'"
+    );
+
+    assert_eq!(literal(&f64::NAN), Err(fmt::Error));
+    assert_eq!(literal(&f32::INFINITY), Err(fmt::Error));
+    assert_eq!(literal(&Some(f64::NEG_INFINITY)), Err(fmt::Error));
+
+    use fmt::Write;
+    let mut s = String::new();
+    assert!(write!(s, "{}", SqlLiteral(&f64::NAN)).is_err());
+}
+
+#[test]
+fn test_in_list() {
+    assert_eq!(in_list(&[1, 2, 3]).unwrap(), "(1, 2, 3)");
+    assert_eq!(in_list(&[-7i64]).unwrap(), "(-7)");
+    assert_eq!(in_list(&["it's", "x"]).unwrap(), "(R'it''s', R'x')");
+    assert_eq!(in_list(&[Some("a"), None]).unwrap(), "(R'a', NULL)");
+    assert_eq!(
+        in_list(&[Value::Int(1), Value::from("o'k"), Value::Bool(true)]).unwrap(),
+        "(1, R'o''k', true)"
+    );
+    assert_eq!(in_list::<i32>(&[]).unwrap(), "(NULL)");
+    assert_eq!(in_list::<Value>(&[]).unwrap(), "(NULL)");
+
+    // no literals for NaN and infinity
+    assert_eq!(in_list(&[1.0, f64::NAN]), Err(fmt::Error));
+    assert_eq!(in_list(&[f32::INFINITY]), Err(fmt::Error));
+    assert_eq!(in_list(&[Some(f64::NEG_INFINITY), None]), Err(fmt::Error));
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+use std::{error, fmt, ops::Deref, sync::Arc};
+
+/// Holds the underlying cause of an error in a way that implements PartialEq,
+/// Eq and Clone.
+///
+/// It deliberately does not implement [`std::error::Error`] itself but
+/// dereferences to the wrapped error instead, so that
+/// [`source()`](`std::error::Error::source`) yields the original error and
+/// callers can downcast it.
+#[derive(Clone)]
+pub struct ErrorSource(Arc<dyn error::Error + Send + Sync + 'static>);
+
+impl ErrorSource {
+    /// Wrap the given error.
+    pub fn new(err: impl error::Error + Send + Sync + 'static) -> Self {
+        ErrorSource(Arc::new(err))
+    }
+}
+
+impl Deref for ErrorSource {
+    type Target = dyn error::Error + Send + Sync + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl fmt::Debug for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Two sources are considered equal if they render the same message.
+impl PartialEq for ErrorSource {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Eq for ErrorSource {}
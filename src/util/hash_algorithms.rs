@@ -23,21 +23,70 @@ fn new_hasher<T: Digest + DynDigest + Default + 'static>() -> Box<dyn DynDigest>
 
 type Algo = fn() -> Box<dyn DynDigest>;
 
-pub fn find_algo(comma_separated_names: &str) -> Option<(&'static str, Algo)> {
-    let algos: &[(&'static str, Algo)] = &[
-        ("RIPEMD160", new_hasher::<ripemd::Ripemd160>),
-        ("SHA512", new_hasher::<sha2::Sha512>),
-        ("SHA384", new_hasher::<sha2::Sha384>),
-        ("SHA256", new_hasher::<sha2::Sha256>),
-        ("SHA224", new_hasher::<sha2::Sha224>),
-        // ("SHA1", new_hasher::<Sha1>),
-    ];
-    for name in comma_separated_names.split(',') {
-        for (n, a) in algos {
-            if *n == name {
-                return Some((n, *a));
-            }
-        }
+const ALGORITHMS: &[(&str, Algo)] = &[
+    ("RIPEMD160", new_hasher::<ripemd::Ripemd160>),
+    ("SHA512", new_hasher::<sha2::Sha512>),
+    ("SHA384", new_hasher::<sha2::Sha384>),
+    ("SHA256", new_hasher::<sha2::Sha256>),
+    ("SHA224", new_hasher::<sha2::Sha224>),
+    #[cfg(feature = "sha1")]
+    ("SHA1", new_hasher::<sha1::Sha1>),
+];
+
+fn lookup(name: &str) -> Option<(&'static str, Algo)> {
+    let found = ALGORITHMS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .copied();
+    if let Some(("SHA1", _)) = found {
+        warn!("using insecure hash algorithm SHA1");
     }
-    None
+    found
+}
+
+pub fn find_algo(comma_separated_names: &str) -> Option<(&'static str, Algo)> {
+    comma_separated_names.split(',').find_map(lookup)
+}
+
+/// Like [`find_algo`] but first try the algorithms in `preferred`, in order,
+/// if the server offers them.
+pub fn find_preferred_algo(
+    preferred: &str,
+    comma_separated_names: &str,
+) -> Option<(&'static str, Algo)> {
+    let offered = |name: &str| {
+        comma_separated_names
+            .split(',')
+            .any(|n| n.eq_ignore_ascii_case(name))
+    };
+    preferred
+        .split(',')
+        .map(str::trim)
+        .filter(|name| offered(name))
+        .find_map(lookup)
+        .or_else(|| find_algo(comma_separated_names))
+}
+
+#[test]
+fn test_find_preferred_algo() {
+    let server = "RIPEMD160,SHA512,SHA256,PROT10";
+    let name = |found: Option<(&'static str, Algo)>| found.map(|(n, _)| n);
+
+    assert_eq!(name(find_algo(server)), Some("RIPEMD160"));
+    assert_eq!(name(find_preferred_algo("", server)), Some("RIPEMD160"));
+    assert_eq!(name(find_preferred_algo("SHA512", server)), Some("SHA512"));
+    assert_eq!(name(find_preferred_algo("sha256", server)), Some("SHA256"));
+    assert_eq!(
+        name(find_preferred_algo("SHA384, SHA256", server)),
+        Some("SHA256")
+    );
+    // not offered by the server, fall back to server order
+    assert_eq!(
+        name(find_preferred_algo("SHA224", server)),
+        Some("RIPEMD160")
+    );
+    assert_eq!(name(find_preferred_algo("SHA512", "PROT10")), None);
+
+    let sha1_available = cfg!(feature = "sha1");
+    assert_eq!(find_algo("SHA1").is_some(), sha1_available);
 }
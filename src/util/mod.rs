@@ -12,3 +12,5 @@ pub mod referencedata;
 pub mod hash_algorithms;
 
 pub mod ioerror;
+
+pub mod errorsource;
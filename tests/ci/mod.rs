@@ -8,7 +8,12 @@
 
 mod context;
 
+#[cfg(feature = "arrow")]
+mod test_arrow;
 mod test_connecting;
+mod test_cursor;
+#[cfg(feature = "derive")]
+mod test_derive;
 mod test_resulttypes;
 
 use anyhow::Result as AResult;
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+use arrow::{
+    array::{
+        Array, BooleanArray, Date32Array, Decimal128Array, Int32Array, StringArray,
+        TimestampMicrosecondArray,
+    },
+    datatypes::{DataType, TimeUnit},
+};
+
+use crate::{context::with_shared_cursor, AResult};
+
+#[test]
+fn test_to_record_batch() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute(
+            "SELECT * FROM (VALUES \
+             (1, true, CAST(1.25 AS DECIMAL(5, 2)), DATE '1970-01-02', TIMESTAMP '1970-01-01 00:00:01', 'one'), \
+             (NULL, NULL, NULL, NULL, NULL, NULL) \
+             ) AS t(i, b, d, dt, ts, s)",
+        )?;
+        let batch = cursor.to_record_batch()?;
+        assert_eq!(batch.num_rows(), 2);
+
+        let schema = batch.schema();
+        let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["i", "b", "d", "dt", "ts", "s"]);
+        assert_eq!(schema.field(2).data_type(), &DataType::Decimal128(5, 2));
+        assert_eq!(
+            schema.field(4).data_type(),
+            &DataType::Timestamp(TimeUnit::Microsecond, None)
+        );
+
+        let col = |i: usize| batch.column(i).as_any();
+        let ints = col(0).downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(ints.value(0), 1);
+        assert!(ints.is_null(1));
+        let bools = col(1).downcast_ref::<BooleanArray>().unwrap();
+        assert!(bools.value(0));
+        let decimals = col(2).downcast_ref::<Decimal128Array>().unwrap();
+        assert_eq!(decimals.value(0), 125);
+        let dates = col(3).downcast_ref::<Date32Array>().unwrap();
+        assert_eq!(dates.value(0), 1);
+        let timestamps = col(4).downcast_ref::<TimestampMicrosecondArray>().unwrap();
+        assert_eq!(timestamps.value(0), 1_000_000);
+        let strings = col(5).downcast_ref::<StringArray>().unwrap();
+        assert_eq!(strings.value(0), "one");
+        for i in 0..batch.num_columns() {
+            assert!(batch.column(i).is_null(1));
+        }
+        Ok(())
+    })
+}
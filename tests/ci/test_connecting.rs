@@ -7,9 +7,21 @@
 // Copyright 2024 MonetDB Foundation
 
 use crate::{get_server, AResult};
-use claims::assert_some;
-use monetdb::{parms::Parm, Connection, CursorResult, Parameters};
-use std::{io, net::TcpListener};
+use claims::{assert_matches, assert_some};
+use monetdb::{
+    parms::Parm, ConnectError, Connection, CursorError, CursorResult, Endian, Parameters,
+    QueryEvent, QueryEventKind,
+};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    iter,
+    net::{TcpListener, TcpStream},
+    ops::ControlFlow,
+    sync::{mpsc, Arc, Mutex},
+    thread::JoinHandle,
+    time::Duration,
+};
 
 #[test]
 fn test_connect() -> AResult<()> {
@@ -31,6 +43,21 @@ fn test_metadata() -> AResult<()> {
     assert!(version.0 >= 11);
     assert!(version.1 >= 1);
     assert_some!(metadata.env("monet_release"));
+    assert_eq!(metadata.monet_release(), metadata.env("monet_release"));
+    assert_eq!(metadata.gdk_dbpath(), metadata.env("gdk_dbpath"));
+    assert!(metadata.max_clients().unwrap() > 0);
+    Ok(())
+}
+
+#[test]
+fn test_refresh_metadata() -> AResult<()> {
+    let ctx = get_server();
+    let mut conn = Connection::new(ctx.parms())?;
+    let cached = conn.metadata()?;
+    let refreshed = conn.refresh_metadata()?;
+    assert_eq!(refreshed.version(), cached.version());
+    assert_eq!(refreshed.env("gdk_dbpath"), cached.env("gdk_dbpath"));
+    assert_eq!(conn.metadata()?.version(), refreshed.version());
     Ok(())
 }
 
@@ -125,6 +152,59 @@ fn run_redirect_server(listener: TcpListener, redirect_to: String) {
     }
 }
 
+#[test]
+fn test_proxy_restart() -> AResult<()> {
+    // Spawn a fake monetdbd that proxies twice before the fake mserver
+    // takes over on the same connection. Return the user names that were
+    // sent in the responses.
+    let (server, parms) = fake_listener(move |listener| -> io::Result<Vec<String>> {
+        let (mut conn, _peer) = listener.accept()?;
+        let mut users = vec![];
+        let mut login = |challenge: &str, reply: &str| -> io::Result<()> {
+            let response = serve_login(&mut conn, challenge, reply)?;
+            users.push(response.split(':').nth(1).unwrap_or_default().to_string());
+            Ok(())
+        };
+        let proxy_challenge = "BANANA:merovingian:9:RIPEMD160,SHA512:LIT:SHA512:";
+        let proxy_restart = "^mapi:merovingian://proxy?database=demo";
+        login(proxy_challenge, proxy_restart)?;
+        login(proxy_challenge, proxy_restart)?;
+        login(CHALLENGE, "")?;
+        Ok(users)
+    })?;
+
+    let parms = parms
+        .with_database("demo")?
+        .with_user("alice")?
+        .with_password("secret")?;
+    let conn = Connection::new(parms)?;
+    conn.close();
+
+    let users = server.join().unwrap()?;
+    assert_eq!(users, ["merovingian", "merovingian", "alice"]);
+    Ok(())
+}
+
+#[test]
+fn test_endless_proxy_restart() -> AResult<()> {
+    // Spawn a fake monetdbd that keeps restarting the authentication
+    let (_server, parms) = fake_listener(move |listener| -> io::Result<()> {
+        let (mut conn, _peer) = listener.accept()?;
+        loop {
+            serve_login(
+                &mut conn,
+                "BANANA:merovingian:9:RIPEMD160,SHA512:LIT:SHA512:",
+                "^mapi:merovingian://proxy?database=demo",
+            )?;
+        }
+    })?;
+
+    let parms = parms.with_database("demo")?;
+    let result = Connection::new(parms).map(|_| ());
+    assert_eq!(result, Err(ConnectError::TooManyRedirects));
+    Ok(())
+}
+
 fn send_msg(mut conn: impl io::Write, msg: &str) -> io::Result<()> {
     assert!(msg.len() < 8190);
     let hdr_val = 2 * msg.len() as u16 + 1;
@@ -155,3 +235,1274 @@ fn recv_msg(mut conn: impl io::Read) -> io::Result<String> {
     let s: String = String::from_utf8_lossy(&buffer).into();
     Ok(s)
 }
+
+/// The challenge of the fake servers, which offer protocol 9 with handshake
+/// options.
+const CHALLENGE: &str = "abcdefgh:mserver:9:RIPEMD160,SHA512:LIT:SHA512:sql=9:";
+
+/// Play the server side of the login: send the challenge, receive the
+/// response and accept it with the given welcome message. Return the
+/// response.
+fn serve_login(conn: &mut TcpStream, challenge: &str, welcome: &str) -> io::Result<String> {
+    send_msg(&mut *conn, challenge)?;
+    let response = recv_msg(&mut *conn)?;
+    send_msg(conn, welcome)?;
+    Ok(response)
+}
+
+/// Spawn a fake server that accepts a single connection, lets the client log
+/// in with [`CHALLENGE`] and then hands the connection to `f`.
+fn fake_server<T, F>(f: F) -> AResult<(JoinHandle<io::Result<T>>, Parameters)>
+where
+    F: FnOnce(TcpStream) -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    fake_listener(move |listener| {
+        let (mut conn, _peer) = listener.accept()?;
+        serve_login(&mut conn, CHALLENGE, "")?;
+        f(conn)
+    })
+}
+
+/// Spawn a fake server thread that is handed a listener on a free local
+/// port. Return the thread and Parameters that connect to the listener.
+fn fake_listener<T, F>(f: F) -> AResult<(JoinHandle<io::Result<T>>, Parameters)>
+where
+    F: FnOnce(TcpListener) -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let host = "127.0.0.1";
+    let listener = TcpListener::bind((host, 0))?;
+    let port = listener.local_addr()?.port();
+    let server = std::thread::spawn(move || f(listener));
+    let parms = Parameters::default()
+        .with_host(host)?
+        .with_port(port)?
+        .with_user("monetdb")?
+        .with_password("monetdb")?;
+    Ok((server, parms))
+}
+
+#[test]
+fn test_autocommit_off() -> AResult<()> {
+    let ctx = get_server();
+    let parms: Parameters = ctx.parms().with_autocommit(false)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    // ROLLBACK is rejected by the server when autocommit is enabled
+    cursor.execute("ROLLBACK")?;
+    Ok(())
+}
+
+#[test]
+fn test_autocommit_confirmation() -> AResult<()> {
+    // Spawn a fake server without handshake options, so autocommit=off is
+    // sent as Xauto_commit, followed by a ROLLBACK to check the mode. It
+    // answers them differently on each connection.
+    let responses = [
+        ("", "&4 f\n"),
+        ("&4 f\n", "&4 f\n"),
+        ("&4 t\n", "&4 f\n"),
+        ("&42 what?\n", "&4 f\n"),
+        ("", "!2DM30!ROLLBACK: not allowed in auto commit mode\n"),
+        ("", ""),
+    ];
+    let (server, parms) = fake_listener(move |listener| -> io::Result<()> {
+        let challenge = CHALLENGE.trim_end_matches("sql=9:");
+        for (auto_commit, rollback) in responses {
+            let (mut conn, _peer) = listener.accept()?;
+            serve_login(&mut conn, challenge, "")?;
+            while let Ok(msg) = recv_msg(&mut conn) {
+                let reply = if msg.starts_with("Xauto_commit") {
+                    auto_commit
+                } else if msg.starts_with("sROLLBACK") {
+                    rollback
+                } else {
+                    ""
+                };
+                send_msg(&mut conn, reply)?;
+            }
+        }
+        Ok(())
+    })?;
+    let parms = parms.with_autocommit(false)?;
+
+    // Xauto_commit not confirmed but ROLLBACK is
+    Connection::new(parms.clone())?;
+    // confirmed
+    Connection::new(parms.clone())?;
+    // contradicted
+    let err = Connection::new(parms.clone()).err().unwrap();
+    assert_matches!(&err, ConnectError::Rejected(msg) if msg.contains("server reports autocommit=on"));
+    // not understood
+    let err = Connection::new(parms.clone()).err().unwrap();
+    assert_matches!(&err, ConnectError::Rejected(msg) if msg.contains("unexpected response to Xauto_commit"));
+    // the server is still in autocommit mode
+    let err = Connection::new(parms.clone()).err().unwrap();
+    assert_matches!(&err, ConnectError::Rejected(msg) if msg.contains("not allowed in auto commit mode"));
+    // ROLLBACK must report the mode
+    let err = Connection::new(parms).err().unwrap();
+    assert_matches!(&err, ConnectError::Rejected(msg) if msg.contains("unexpected response to ROLLBACK"));
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_autocommit_handshake_option_checked() -> AResult<()> {
+    // This fake server accepts the handshake options but ignores them, so
+    // the connection is still in autocommit mode.
+    let (server, parms) = fake_server(|mut conn| -> io::Result<Vec<String>> {
+        let mut received = vec![];
+        while let Ok(msg) = recv_msg(&mut conn) {
+            let reply = if msg.starts_with("sROLLBACK") {
+                "!2DM30!ROLLBACK: not allowed in auto commit mode\n"
+            } else {
+                ""
+            };
+            received.push(msg);
+            send_msg(&mut conn, reply)?;
+        }
+        Ok(received)
+    })?;
+    let parms = parms.with_autocommit(false)?;
+
+    let err = Connection::new(parms).err().unwrap();
+    assert_matches!(&err, ConnectError::Rejected(msg) if msg.contains("not allowed in auto commit mode"));
+    let received = server.join().unwrap()?;
+    assert!(
+        !received.iter().any(|m| m.starts_with("Xauto_commit")),
+        "{received:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_schema() -> AResult<()> {
+    let ctx = get_server();
+    let parms: Parameters = ctx.parms().with_schema("tmp")?;
+    let conn = Connection::new(parms)?;
+    assert_eq!(conn.current_schema()?, "tmp");
+
+    let parms: Parameters = ctx.parms().with_schema("no such schema")?;
+    let result = Connection::new(parms).map(|_| ());
+    assert_matches!(result, Err(ConnectError::Rejected(_)));
+    Ok(())
+}
+
+#[test]
+fn test_init_statement_schema() -> AResult<()> {
+    // init statements run after the schema has been set
+    let ctx = get_server();
+    let parms = ctx
+        .parms()
+        .with_schema("tmp")?
+        .with_init_statement("CREATE TEMPORARY TABLE init_marker(i INT)");
+    let conn = Connection::new(parms)?;
+    assert_eq!(
+        conn.query_opt::<i64>("SELECT COUNT(*) FROM init_marker")?,
+        Some(0)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_current_user() -> AResult<()> {
+    let ctx = get_server();
+    let parms = ctx.parms();
+    let user = parms.get_str(Parm::User)?.into_owned();
+    let conn = Connection::new(parms)?;
+    assert_eq!(conn.current_user()?, user);
+    Ok(())
+}
+
+#[test]
+fn test_query_opt() -> AResult<()> {
+    let conn = Connection::new(get_server().parms())?;
+    assert_eq!(conn.query_opt::<i32>("SELECT 42")?, Some(42));
+    assert_eq!(conn.query_opt::<String>("SELECT 'x' WHERE 1 = 0")?, None);
+    assert_eq!(
+        conn.query_opt::<Option<i32>>("SELECT CAST(NULL AS INT)")?,
+        Some(None)
+    );
+    assert_matches!(
+        conn.query_opt::<i32>("SELECT CAST(NULL AS INT)"),
+        Err(CursorError::Conversion { .. })
+    );
+    assert_matches!(
+        conn.query_opt::<i64>("SELECT * FROM sys.generate_series(0, 1000)"),
+        Err(CursorError::TooManyRows)
+    );
+    // the connection is still usable afterwards
+    assert_eq!(conn.query_opt::<i32>("SELECT 43")?, Some(43));
+    Ok(())
+}
+
+#[test]
+fn test_preferred_hash() -> AResult<()> {
+    let ctx = get_server();
+    for hash in ["SHA512", "SHA256,SHA384", "NOSUCHALGO"] {
+        let parms = ctx.parms().with_hash(hash)?;
+        let conn = Connection::new(parms)?;
+        conn.current_user()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_session_id() -> AResult<()> {
+    let ctx = get_server();
+    let conn = Connection::new(ctx.parms())?;
+    let id = conn.session_id()?;
+    assert_eq!(conn.session_id()?, id);
+
+    let mut cursor = conn.cursor();
+    cursor.execute(&format!(
+        "SELECT COUNT(*) FROM sys.sessions WHERE sessionid = {id}"
+    ))?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_i64(0)?, Some(1));
+    Ok(())
+}
+
+#[test]
+fn test_client_library() -> AResult<()> {
+    let ctx = get_server();
+    let parms = ctx.parms().with_client_library("my-lib 1.0")?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT client FROM sys.sessions WHERE sessionid = sys.current_sessionid()")?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_str(0)?, Some("my-lib 1.0"));
+    Ok(())
+}
+
+#[test]
+fn test_oversized_challenge() -> AResult<()> {
+    // Spawn a fake server that sends a challenge that is way too long
+    let (_server, parms) = fake_listener(|listener| {
+        let (mut conn, _peer) = listener.accept()?;
+        send_msg(&mut conn, &"A".repeat(6000))
+    })?;
+    let result = Connection::new(parms).map(|_| ());
+    assert_eq!(
+        result,
+        Err(ConnectError::InvalidChallenge(
+            "challenge exceeds 5000 bytes".into()
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_tls_expected() -> AResult<()> {
+    // Spawn a fake TLS server that rejects our plaintext connection
+    let (_server, parms) = fake_listener(|listener| {
+        let (mut conn, _peer) = listener.accept()?;
+        // TLS 1.2 alert record: fatal, handshake failure
+        conn.write_all(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28])
+    })?;
+    let result = Connection::new(parms).map(|_| ());
+    assert_eq!(result, Err(ConnectError::ServerExpectsTls));
+    Ok(())
+}
+
+#[test]
+fn test_connection_lost_mid_result() -> AResult<()> {
+    // Spawn a fake server that sends the first row of a result set and hangs
+    // up when asked for more
+    let (_server, parms) = fake_server(move |mut conn| -> io::Result<()> {
+        recv_msg(&mut conn)?;
+        send_msg(
+            &mut conn,
+            concat!(
+                "&1 1 3 1 1\n",
+                "% .x # table_name\n",
+                "% x # name\n",
+                "% int # type\n",
+                "% 1 # length\n",
+                "% 0 0 # typesizes\n",
+                "[ 1\t]\n",
+            ),
+        )?;
+        let fetch = recv_msg(&mut conn)?;
+        assert!(fetch.starts_with("Xexport 1 1 "), "{fetch:?}");
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT x FROM three_rows")?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_i32(0)?, Some(1));
+    assert!(!cursor.is_closed());
+    assert_matches!(
+        cursor.next_row(),
+        Err(CursorError::ConnectionLostMidResult(_))
+    );
+    assert!(cursor.is_closed());
+    assert!(conn.is_closed());
+    assert_matches!(cursor.next_row(), Err(CursorError::Closed));
+    assert_matches!(conn.cursor().execute("SELECT 1"), Err(CursorError::Closed));
+    Ok(())
+}
+
+#[test]
+fn test_drop_flushes_pending_close() -> AResult<()> {
+    // Spawn a fake server that sends a partial result set and reports the
+    // next message it receives
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<String> {
+        recv_msg(&mut conn)?;
+        send_msg(
+            &mut conn,
+            concat!(
+                "&1 7 3 1 1\n",
+                "% .x # table_name\n",
+                "% x # name\n",
+                "% int # type\n",
+                "% 1 # length\n",
+                "% 0 0 # typesizes\n",
+                "[ 1\t]\n",
+            ),
+        )?;
+        recv_msg(&mut conn)
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT x FROM three_rows")?;
+    assert_eq!(cursor.result_id(), Some(7));
+    // skipping the result set queues an Xclose
+    assert!(!cursor.next_reply()?);
+    assert_eq!(cursor.result_id(), None);
+    drop(conn);
+    drop(cursor);
+
+    let received = server.join().unwrap()?;
+    assert_eq!(received.trim_end(), "Xclose 7");
+    Ok(())
+}
+
+#[test]
+fn test_raw_command() -> AResult<()> {
+    // Spawn a fake server that echoes the first message after the login,
+    // followed by an error
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<()> {
+        let msg = recv_msg(&mut conn)?;
+        send_msg(&mut conn, &format!("{msg}!42000!no such thing\n"))?;
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let reply = conn.raw_command(b"Xfrobnicate 42")?;
+    assert_eq!(reply, b"Xfrobnicate 42!42000!no such thing\n");
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_large_statement() -> AResult<()> {
+    // A statement that spans several MAPI blocks of 8190 bytes must arrive
+    // intact
+    let values = (0..3000).map(|i| format!("({i})")).collect::<Vec<_>>();
+    let sql = format!("INSERT INTO foo VALUES {}", values.join(","));
+    assert!(sql.len() > 2 * 8190);
+
+    // Spawn a fake server that returns the statement it received
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<Vec<String>> {
+        let mut msgs = vec![];
+        for _ in 0..2 {
+            msgs.push(recv_msg(&mut conn)?);
+            send_msg(&mut conn, "&2 3000 -1\n")?;
+        }
+        Ok(msgs)
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    cursor.execute(&sql)?;
+    assert_eq!(cursor.affected_rows(), Some(3000));
+
+    // The same statement sent in pieces
+    let observed = Arc::new(Mutex::new(String::new()));
+    let observed_clone = Arc::clone(&observed);
+    conn.set_query_observer(Box::new(move |event: &QueryEvent| {
+        *observed_clone.lock().unwrap() = event.sql.to_string();
+    }));
+    let parts = iter::once("INSERT INTO foo VALUES ".to_string()).chain(
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if i == 0 { v.clone() } else { format!(",{v}") }),
+    );
+    cursor.execute_parts(parts)?;
+    assert_eq!(cursor.affected_rows(), Some(3000));
+    assert!(observed
+        .lock()
+        .unwrap()
+        .starts_with("INSERT INTO foo VALUES (0),(1)"));
+
+    let received = server.join().unwrap()?;
+    assert_eq!(received, [format!("s{sql}\n;"), format!("s{sql}\n;")]);
+    Ok(())
+}
+
+#[test]
+fn test_unlimited_reply_size() -> AResult<()> {
+    let ctx = get_server();
+    let parms = ctx.parms().with_replysize(0)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor().with_retain_blocks(true);
+    cursor.execute("SELECT * FROM sys.generate_series(0, 1000)")?;
+    assert!(!cursor.is_streaming());
+    let values = cursor.collect_column::<i32>(0)?;
+    assert_eq!(values.len(), 1000);
+    // everything arrived in the initial reply
+    assert_eq!(cursor.retained_blocks().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_maxprefetch() -> AResult<()> {
+    let ctx = get_server();
+    let parms = ctx.parms().with_replysize(10)?.with_maxprefetch(5)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor().with_retain_blocks(true);
+    cursor.execute("SELECT * FROM sys.generate_series(0, 100)")?;
+    assert!(cursor.is_streaming());
+    let values = cursor.collect_column::<i32>(0)?;
+    assert_eq!(values.len(), 100);
+    // the initial reply holds 10 rows, the other 90 come 5 at a time
+    assert_eq!(cursor.retained_blocks().len(), 1 + 90 / 5);
+    Ok(())
+}
+
+#[test]
+fn test_max_result_bytes() -> AResult<()> {
+    const HEADERS: &str = concat!(
+        "% .s # table_name\n",
+        "% s # name\n",
+        "% varchar # type\n",
+        "% 40 # length\n",
+    );
+    let long_row = format!("[ \"{}\"\t]\n", "x".repeat(25));
+    let big_reply = format!("&1 7 10 1 5\n{HEADERS}{}", long_row.repeat(5));
+    let small_reply = format!("&1 8 10 1 1\n{HEADERS}[ \"short\"\t]\n");
+    let big_page = format!("&6 8 1 5 1\n{}", long_row.repeat(5));
+    let limit = 200;
+    assert!(big_reply.len() > limit);
+    assert!(small_reply.len() < limit && big_page.len() < limit);
+    assert!(small_reply.len() + big_page.len() > limit);
+
+    // Spawn a fake server that sends a reply that is too large, then one
+    // that fits but is followed by a batch of rows that does not, and
+    // returns the commands it receives
+    let replies = [big_reply.clone(), small_reply.clone(), big_page.clone()];
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<Vec<String>> {
+        let mut commands = vec![];
+        for reply in replies {
+            commands.push(recv_msg(&mut conn)?);
+            if commands.last().unwrap().starts_with("Xclose") {
+                send_msg(&mut conn, "")?;
+                commands.push(recv_msg(&mut conn)?);
+            }
+            send_msg(&mut conn, &reply)?;
+        }
+        commands.push(recv_msg(&mut conn)?);
+        send_msg(&mut conn, "")?;
+        commands.push(recv_msg(&mut conn)?);
+        send_msg(&mut conn, "&3 1 1\n")?;
+        Ok(commands)
+    })?;
+
+    let parms = parms
+        .with_replysize(5)?
+        .with_max_result_bytes(limit as i64)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+
+    let err = cursor.execute("SELECT s FROM big").unwrap_err();
+    assert_eq!(
+        err,
+        CursorError::ResultTooLarge {
+            bytes: big_reply.len(),
+            limit
+        }
+    );
+
+    // the connection is still in sync
+    cursor.execute("SELECT s FROM small")?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_str(0)?, Some("short"));
+    let err = cursor.next_row().unwrap_err();
+    assert_eq!(
+        err,
+        CursorError::ResultTooLarge {
+            bytes: small_reply.len() + big_page.len(),
+            limit
+        }
+    );
+    cursor.execute("CREATE TABLE foo(i INT)")?;
+
+    let commands = server.join().unwrap()?;
+    assert_eq!(
+        commands,
+        [
+            "sSELECT s FROM big\n;",
+            "Xclose 7\n",
+            "sSELECT s FROM small\n;",
+            "Xexport 8 1 5",
+            "Xclose 8\n",
+            "sCREATE TABLE foo(i INT)\n;",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_fetch_metadata_on_connect() -> AResult<()> {
+    // Spawn a fake server that answers the metadata query right after the
+    // login and returns it
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<String> {
+        let query = recv_msg(&mut conn)?;
+        send_msg(
+            &mut conn,
+            concat!(
+                "&1 7 2 2 2\n",
+                "% .env,\t.env # table_name\n",
+                "% name,\tvalue # name\n",
+                "% varchar,\tvarchar # type\n",
+                "% 15,\t7 # length\n",
+                "[ \"monet_version\",\t\"11.51.3\"\t]\n",
+                "[ \"monet_release\",\t\"Aug2024\"\t]\n",
+            ),
+        )?;
+        Ok(query)
+    })?;
+
+    let parms = parms.with_fetch_metadata_on_connect(true)?;
+    let mut conn = Connection::new(parms)?;
+    let query = server.join().unwrap()?;
+    assert!(query.contains("sys.environment"), "{query}");
+
+    // served from the cache, no second query
+    let statements = conn.stats().statements;
+    let metadata = conn.metadata()?;
+    assert_eq!(metadata.version(), (11, 51, 3));
+    assert_eq!(metadata.monet_release(), Some("Aug2024"));
+    assert_eq!(conn.stats().statements, statements);
+    Ok(())
+}
+
+#[test]
+fn test_poll_query() -> AResult<()> {
+    // Spawn a fake server that answers the same query three times with an
+    // increasing count
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<Vec<String>> {
+        let mut queries = vec![];
+        for count in 1..=3 {
+            queries.push(recv_msg(&mut conn)?);
+            let reply = format!(
+                "&1 7 1 1 1\n% .t # table_name\n% n # name\n% int # type\n% 1 # length\n[ {count}\t]\n"
+            );
+            send_msg(&mut conn, &reply)?;
+        }
+        Ok(queries)
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let mut seen = vec![];
+    let result = conn.poll_query(
+        "SELECT COUNT(*) FROM events",
+        Duration::from_millis(1),
+        |cursor| {
+            assert!(cursor.next_row()?);
+            let n = cursor.get_i32(0)?.unwrap();
+            seen.push(n);
+            Ok(if n == 3 {
+                ControlFlow::Break("done")
+            } else {
+                ControlFlow::Continue(())
+            })
+        },
+    )?;
+    assert_eq!(result, "done");
+    assert_eq!(seen, [1, 2, 3]);
+
+    let queries = server.join().unwrap()?;
+    assert_eq!(queries, ["sSELECT COUNT(*) FROM events\n;"; 3]);
+    Ok(())
+}
+
+#[test]
+fn test_raw_result_block() -> AResult<()> {
+    // Spawn a fake server that includes three of the four rows in the
+    // initial reply
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<String> {
+        recv_msg(&mut conn)?;
+        send_msg(
+            &mut conn,
+            concat!(
+                "&1 7 4 2 3\n",
+                "% .t,\t.t # table_name\n",
+                "% i,\ts # name\n",
+                "% int,\tvarchar # type\n",
+                "% 1,\t5 # length\n",
+                "[ 1,\t\"one\"\t]\n",
+                "[ 2,\t\"t\\\"wo\"\t]\n",
+                "[ 3,\tNULL\t]\n",
+            ),
+        )?;
+        let export = recv_msg(&mut conn)?;
+        send_msg(&mut conn, "&6 7 2 1 3\n[ 4,\t\"four\"\t]\n")?;
+        Ok(export)
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT i, s FROM t")?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_str(1)?, Some("one"));
+
+    // the rest of the initial reply, escapes intact
+    let block = cursor.raw_result_block()?;
+    assert_eq!(block, b"[ 2,\t\"t\\\"wo\"\t]\n[ 3,\tNULL\t]\n");
+    // then the next batch
+    let block = cursor.raw_result_block()?;
+    assert_eq!(block, b"[ 4,\t\"four\"\t]\n");
+    assert_eq!(cursor.raw_result_block()?, b"");
+    assert!(!cursor.next_row()?);
+    assert_eq!(conn.stats().rows_fetched, 4);
+
+    let export = server.join().unwrap()?;
+    assert!(export.starts_with("Xexport 7 3 "), "{export}");
+    Ok(())
+}
+
+#[test]
+fn test_retain_blocks() -> AResult<()> {
+    const RESULT_SET: &str = concat!(
+        "&1 7 2 1 1\n",
+        "% .x # table_name\n",
+        "% x # name\n",
+        "% int # type\n",
+        "% 1 # length\n",
+        "[ 1\t]\n",
+    );
+    const EXPORT: &str = "&6 7 1 1 1\n[ 2\t]\n";
+
+    // Spawn a fake server that sends the second row of the result set
+    // separately, then answers a second query
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<()> {
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, RESULT_SET)?;
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, EXPORT)?;
+        // the Xclose of the result set is sent along with the next query
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, "")?;
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, "&2 1 -1\n")?;
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor().with_retain_blocks(true);
+    cursor.execute("SELECT x FROM t")?;
+    let values = cursor.collect_column::<i32>(0)?;
+    assert_eq!(values, [Some(1), Some(2)]);
+    assert_eq!(
+        cursor.retained_blocks(),
+        [RESULT_SET.as_bytes(), EXPORT.as_bytes()]
+    );
+
+    // executing again replaces them
+    cursor.execute("DELETE FROM t")?;
+    assert_eq!(cursor.retained_blocks(), [b"&2 1 -1\n"]);
+
+    cursor.set_retain_blocks(false);
+    assert!(cursor.retained_blocks().is_empty());
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_interleaved_cursors_command_order() -> AResult<()> {
+    // The rows themselves are checked against the real server in
+    // test_cursor.rs. Spawn a fake server that sends the result sets of two
+    // queries in parts and records the commands it receives.
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<Vec<String>> {
+        let mut received = vec![];
+        let header = |id, total, included| {
+            format!("&1 {id} {total} 1 {included}\n% .x # table_name\n% x # name\n% int # type\n% 3 # length\n")
+        };
+        let replies = [
+            header(7, 4, 2) + "[ 1\t]\n[ 2\t]\n",
+            header(8, 3, 1) + "[ 100\t]\n",
+            "&6 8 1 1 1\n[ 200\t]\n".to_string(),
+            // reply to the Xclose 8 queued when cursor B is dropped
+            "".to_string(),
+            "&6 7 1 2 2\n[ 3\t]\n[ 4\t]\n".to_string(),
+        ];
+        for reply in replies {
+            received.push(recv_msg(&mut conn)?);
+            send_msg(&mut conn, &reply)?;
+        }
+        Ok(received)
+    })?;
+
+    let conn = Connection::new(parms)?;
+
+    // cursor A stops in the middle of its result set
+    let mut a = conn.cursor();
+    a.execute("SELECT x FROM a")?;
+    assert!(a.next_row()?);
+
+    // cursor B executes a query and fetches part of its result set, leaving
+    // the rest unread
+    let mut b = conn.cursor();
+    b.execute("SELECT x FROM b")?;
+    assert!(b.next_row()?);
+    assert!(b.next_row()?);
+    drop(b);
+
+    // the Xclose for B goes out before A fetches the rest
+    while a.next_row()? {}
+
+    let received = server.join().unwrap()?;
+    let received: Vec<&str> = received.iter().map(|r| r.trim_end()).collect();
+    assert_eq!(
+        received,
+        [
+            "sSELECT x FROM a\n;",
+            "sSELECT x FROM b\n;",
+            "Xexport 8 1 2",
+            "Xclose 8",
+            "Xexport 7 2 2",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_cow_str() -> AResult<()> {
+    // Spawn a fake server that sends a string containing an octal escape
+    // that is not valid UTF-8
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<()> {
+        recv_msg(&mut conn)?;
+        send_msg(
+            &mut conn,
+            concat!(
+                "&1 7 1 3 1\n",
+                "% .t,\t.t,\t.t # table_name\n",
+                "% a,\tb,\tc # name\n",
+                "% varchar,\tvarchar,\tvarchar # type\n",
+                "% 3,\t3,\t3 # length\n",
+                "[ \"abc\",\t\"x\\377y\",\tNULL\t]\n",
+            ),
+        )?;
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT a, b, c FROM t")?;
+    assert!(cursor.next_row()?);
+
+    let a = cursor.get_cow_str(0)?.unwrap();
+    assert_matches!(a, Cow::Borrowed("abc"));
+    assert_matches!(cursor.get_cow_str(1), Err(CursorError::BadReply(_)));
+    assert_eq!(cursor.get_cow_str(2)?, None);
+
+    cursor.set_lossy_utf8(true);
+    let a = cursor.get_cow_str(0)?.unwrap();
+    assert_matches!(a, Cow::Borrowed("abc"));
+    let b = cursor.get_cow_str(1)?.unwrap();
+    assert_matches!(&b, Cow::Owned(_));
+    assert_eq!(b, "x\u{FFFD}y");
+    // the other getters still reject it
+    assert!(cursor.get_str(1).is_err());
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_connect_with_retry() -> AResult<()> {
+    // Spawn a fake server that drops the first two connections right away,
+    // then accepts the login. After that it rejects the login.
+    let (server, parms) = fake_listener(move |listener| -> io::Result<()> {
+        for _ in 0..2 {
+            let (conn, _peer) = listener.accept()?;
+            drop(conn);
+        }
+        let (mut conn, _peer) = listener.accept()?;
+        serve_login(&mut conn, CHALLENGE, "")?;
+        drop(conn);
+
+        let (mut conn, _peer) = listener.accept()?;
+        serve_login(
+            &mut conn,
+            CHALLENGE,
+            "!InvalidCredentialsException:invalid credentials\n",
+        )?;
+        Ok(())
+    })?;
+
+    let delay = Duration::from_millis(1);
+
+    // two failures are not enough to give up
+    let conn = Connection::connect_with_retry(parms.clone(), 3, delay)?;
+    drop(conn);
+
+    // rejected logins are not retried, otherwise this would hang in accept()
+    let result = Connection::connect_with_retry(parms, 3, delay).map(|_| ());
+    assert_matches!(result, Err(ConnectError::Rejected(_)));
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_connect_with_retry_gives_up() -> AResult<()> {
+    // Spawn a fake server that drops every connection until told to stop
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let (server, parms) = fake_listener(move |listener| -> io::Result<usize> {
+        listener.set_nonblocking(true)?;
+        let mut count = 0;
+        loop {
+            match listener.accept() {
+                Ok(_) => count += 1,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if stop_rx.try_recv().is_ok() {
+                        return Ok(count);
+                    }
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    })?;
+
+    let result = Connection::connect_with_retry(parms, 4, Duration::from_millis(1)).map(|_| ());
+    let err = result.unwrap_err();
+    assert!(err.is_retryable(), "{err}");
+
+    stop_tx.send(())?;
+    assert_eq!(server.join().unwrap()?, 4);
+    Ok(())
+}
+
+#[test]
+fn test_stats() -> AResult<()> {
+    const RESULT_SET: &str = concat!(
+        "&1 7 3 1 3\n",
+        "% .x # table_name\n",
+        "% x # name\n",
+        "% int # type\n",
+        "% 1 # length\n",
+        "% 0 0 # typesizes\n",
+        "[ 1\t]\n",
+        "[ 2\t]\n",
+        "[ 3\t]\n",
+    );
+
+    // Spawn a fake server that answers one query
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<()> {
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, RESULT_SET)?;
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let after_login = conn.stats();
+    assert_eq!(after_login.statements, 0);
+    assert_eq!(after_login.rows_fetched, 0);
+    assert!(after_login.bytes_sent > 0);
+    // every message is a single block with a 2 byte header
+    assert_eq!(after_login.bytes_received, 2 + CHALLENGE.len() as u64 + 2);
+
+    let mut cursor = conn.cursor();
+    let sql = "SELECT x FROM three_rows";
+    cursor.execute(sql)?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.skip_rows(5)?, 2);
+    assert!(!cursor.next_row()?);
+
+    let stats = conn.stats();
+    assert_eq!(stats.statements, 1);
+    assert_eq!(stats.rows_fetched, 3);
+    assert_eq!(
+        stats.bytes_sent - after_login.bytes_sent,
+        2 + format!("s{sql}\n;").len() as u64
+    );
+    assert_eq!(
+        stats.bytes_received - after_login.bytes_received,
+        2 + RESULT_SET.len() as u64
+    );
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_max_result_bytes_failed_delayed_command() -> AResult<()> {
+    // Spawn a fake server without handshake options, so the reply size is
+    // sent as a delayed command. It rejects that and answers the query with
+    // a reply that is too large.
+    let big_reply = format!("&3 1 1\n{}", "% padding\n".repeat(30));
+    let limit = 200;
+    assert!(big_reply.len() > limit);
+    let (server, parms) = fake_listener(move |listener| -> io::Result<()> {
+        let (mut conn, _peer) = listener.accept()?;
+        let challenge = CHALLENGE.trim_end_matches("sql=9:");
+        serve_login(&mut conn, challenge, "")?;
+        while let Ok(msg) = recv_msg(&mut conn) {
+            if msg.starts_with("Xreply_size") {
+                send_msg(&mut conn, "!42000!no reply size for you\n")?;
+            } else if msg.starts_with("sSELECT") {
+                send_msg(&mut conn, &big_reply)?;
+            } else {
+                send_msg(&mut conn, "")?;
+            }
+        }
+        Ok(())
+    })?;
+
+    let parms = parms
+        .with_replysize(42)?
+        .with_max_result_bytes(limit as i64)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+
+    // The size limit is reported, the failed delayed command is available
+    // as the error detail
+    let err = cursor.execute("SELECT 1").unwrap_err();
+    assert!(matches!(err, CursorError::ResultTooLarge { .. }), "{err:?}");
+    let detail = cursor.last_error_detail().unwrap();
+    assert!(detail.contains("delayed reply_size"), "{detail}");
+    assert!(detail.contains("no reply size for you"), "{detail}");
+
+    drop(cursor);
+    drop(conn);
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_failed_delayed_command() -> AResult<()> {
+    // Spawn a fake server without handshake options, so the settings are
+    // sent as delayed commands. It rejects Xreply_size and answers queries
+    // with the statement number.
+    let (server, parms) = fake_listener(move |listener| -> io::Result<()> {
+        let (mut conn, _peer) = listener.accept()?;
+        let challenge = CHALLENGE.trim_end_matches("sql=9:");
+        serve_login(&mut conn, challenge, "")?;
+        let mut statement = 0;
+        while let Ok(msg) = recv_msg(&mut conn) {
+            if msg.starts_with("Xreply_size") {
+                send_msg(&mut conn, "!42000!no reply size for you\n")?;
+            } else if msg.starts_with("sSELECT") {
+                statement += 1;
+                let reply = format!(
+                    "&1 0 1 1 1\n% .x # table_name\n% x # name\n% int # type\n% 1 # length\n[ {statement}\t]\n"
+                );
+                send_msg(&mut conn, &reply)?;
+            } else {
+                send_msg(&mut conn, "&3 1 1\n")?;
+            }
+        }
+        Ok(())
+    })?;
+
+    let parms = parms.with_replysize(42)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+
+    // The first query reports the failure of the delayed command
+    let result = cursor.execute("SELECT 1");
+    let Err(CursorError::DelayedCommand { command, message }) = result else {
+        panic!("expected delayed command error, got {result:?}");
+    };
+    assert_eq!(command, "reply_size");
+    assert!(message.contains("no reply size for you"), "{message}");
+    let detail = cursor.last_error_detail().unwrap();
+    assert!(detail.contains("no reply size for you"), "{detail}");
+
+    // but the result of the query itself is still available
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_i32(0)?, Some(1));
+
+    // The connection is still usable and in sync
+    assert!(!conn.is_closed());
+    cursor.execute("SELECT 2")?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_i32(0)?, Some(2));
+
+    drop(cursor);
+    drop(conn);
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_query_observer() -> AResult<()> {
+    // Spawn a fake server that answers the first query with a result set
+    // and the second with an error
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<()> {
+        recv_msg(&mut conn)?;
+        send_msg(
+            &mut conn,
+            concat!(
+                "&1 7 2 1 2\n",
+                "% .x # table_name\n",
+                "% x # name\n",
+                "% int # type\n",
+                "% 1 # length\n",
+                "% 0 0 # typesizes\n",
+                "[ 1\t]\n",
+                "[ 2\t]\n",
+            ),
+        )?;
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, "!42000!no such table\n")?;
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms)?;
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&events);
+    conn.set_query_observer(Box::new(move |ev| {
+        recorded.lock().unwrap().push((
+            ev.kind,
+            ev.sql.to_string(),
+            ev.result_rows,
+            ev.error.map(|e| e.to_string()),
+        ))
+    }));
+
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT x FROM two_rows")?;
+    while cursor.next_row()? {}
+    assert!(!cursor.next_row()?);
+    assert_matches!(
+        cursor.execute("SELECT x FROM nope"),
+        Err(CursorError::Server(_))
+    );
+    server.join().unwrap()?;
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events[0],
+        (
+            QueryEventKind::Executed,
+            "SELECT x FROM two_rows".into(),
+            Some(2),
+            None
+        )
+    );
+    assert_eq!(
+        events[1],
+        (
+            QueryEventKind::ResultSetDone,
+            "SELECT x FROM two_rows".into(),
+            Some(2),
+            None
+        )
+    );
+    assert_eq!(events[2].0, QueryEventKind::Executed);
+    assert_eq!(events[2].1, "SELECT x FROM nope");
+    assert!(events[2].3.as_ref().unwrap().contains("no such table"));
+    Ok(())
+}
+
+#[test]
+fn test_try_cursor_op() -> AResult<()> {
+    // Spawn a fake server that holds back its reply to the first query
+    // until told to proceed
+    let (received_tx, received_rx) = mpsc::channel();
+    let (proceed_tx, proceed_rx) = mpsc::channel::<()>();
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<()> {
+        recv_msg(&mut conn)?;
+        received_tx.send(()).unwrap();
+        proceed_rx.recv().unwrap();
+        send_msg(&mut conn, "&2 1 -1\n")?;
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, "&2 2 -1\n")?;
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms)?;
+    std::thread::scope(|scope| -> AResult<()> {
+        let background = scope.spawn(|| {
+            let mut cursor = conn.cursor();
+            cursor.execute("UPDATE slow SET x = 1")?;
+            Ok::<_, CursorError>(cursor.affected_rows())
+        });
+        received_rx.recv()?;
+        let busy = conn.try_cursor_op(|cursor| cursor.execute("UPDATE quick SET x = 2"));
+        assert_matches!(busy, Err(CursorError::Busy));
+
+        proceed_tx.send(())?;
+        assert_eq!(background.join().unwrap()?, Some(1));
+        let affected = conn.try_cursor_op(|cursor| {
+            cursor.execute("UPDATE quick SET x = 2")?;
+            Ok(cursor.affected_rows())
+        })?;
+        assert_eq!(affected, Some(2));
+        Ok(())
+    })?;
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_init_statements() -> AResult<()> {
+    // Spawn a fake server that accepts the first init statement and rejects
+    // the second half of the second, and returns the commands it receives
+    let (server, parms) = fake_server(move |mut conn| -> io::Result<Vec<String>> {
+        let mut commands = vec![];
+        commands.push(recv_msg(&mut conn)?);
+        send_msg(&mut conn, "&3 1 1\n")?;
+        commands.push(recv_msg(&mut conn)?);
+        send_msg(&mut conn, "&3 1 1\n!42000!SET: no such optimizer 'bogus'\n")?;
+        Ok(commands)
+    })?;
+
+    let parms = parms
+        .with_init_statement("SET ROLE analyst")
+        .with_init_statement("SET SCHEMA sys; SET optimizer = 'bogus'");
+    let err = Connection::new(parms).err().unwrap();
+    assert_matches!(&err, ConnectError::Rejected(msg) if msg.contains("no such optimizer"));
+
+    let commands = server.join().unwrap()?;
+    assert_eq!(
+        commands,
+        [
+            "sSET ROLE analyst\n;\n",
+            "sSET SCHEMA sys; SET optimizer = 'bogus'\n;\n"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_welcome_message() -> AResult<()> {
+    // Spawn a fake server that greets each of two connections differently
+    let (server, parms) = fake_listener(move |listener| -> io::Result<()> {
+        for greeting in ["#Welcome to the demo cluster\n# build 1234\n", ""] {
+            let (mut conn, _peer) = listener.accept()?;
+            serve_login(&mut conn, CHALLENGE, greeting)?;
+        }
+        Ok(())
+    })?;
+
+    let conn = Connection::new(parms.clone())?;
+    assert_eq!(
+        conn.welcome_message(),
+        Some("Welcome to the demo cluster\nbuild 1234")
+    );
+    let conn = Connection::new(parms)?;
+    assert_eq!(conn.welcome_message(), None);
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_diagnostics() -> AResult<()> {
+    // Spawn a fake big endian server that offers binary level 2 and answers
+    // one query
+    let (server, parms) = fake_listener(move |listener| -> io::Result<()> {
+        let (mut conn, _peer) = listener.accept()?;
+        let challenge = CHALLENGE.replace(":LIT:", ":BIG:") + "BINARY=2:";
+        serve_login(&mut conn, &challenge, "")?;
+        recv_msg(&mut conn)?;
+        send_msg(&mut conn, "&2 1 -1\n")?;
+        Ok(())
+    })?;
+
+    let parms = parms.with_replysize(250)?;
+    let conn = Connection::new(parms)?;
+    conn.cursor().execute("INSERT INTO foo VALUES (1)")?;
+
+    let diag = conn.diagnostics();
+    assert_eq!(diag.protocol_version, 9);
+    assert_eq!(diag.server_endian, Endian::Big);
+    assert_eq!(diag.hash_algo, "RIPEMD160");
+    assert_eq!(diag.prehash_algo, "SHA512");
+    assert_eq!(diag.binary_level, 2);
+    assert_eq!(diag.reply_size, 250);
+    assert!(diag.initial_autocommit);
+    assert_eq!(diag.server_version, None);
+    assert_eq!(diag.session_id, None);
+    assert!(!diag.closed);
+    assert_eq!(diag.stats, conn.stats());
+    assert_eq!(diag.stats.statements, 1);
+
+    let text = diag.to_string();
+    assert!(
+        text.contains("password hash:      RIPEMD160 (prehash SHA512)\n"),
+        "{text}"
+    );
+    assert!(text.contains("server version:     unknown\n"), "{text}");
+    assert!(text.contains("statements:         1\n"), "{text}");
+    assert!(text.contains("initial autocommit: on\n"), "{text}");
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_endian() -> AResult<()> {
+    // Spawn a fake server that claims to be big endian and reports the
+    // login response it receives
+    let (server, parms) = fake_listener(move |listener| -> io::Result<String> {
+        let (mut conn, _peer) = listener.accept()?;
+        let challenge = CHALLENGE.replace(":LIT:", ":BIG:");
+        serve_login(&mut conn, &challenge, "")
+    })?;
+
+    let forced = match Endian::NATIVE {
+        Endian::Big => Endian::Lit,
+        Endian::Lit => Endian::Big,
+    };
+    let parms = parms.with_client_endian(&forced.to_string())?;
+    let conn = Connection::new(parms)?;
+    assert_eq!(conn.server_endian(), Endian::Big);
+
+    let response = server.join().unwrap()?;
+    assert!(response.starts_with(&format!("{forced}:")), "{response:?}");
+    Ok(())
+}
+
+#[test]
+fn test_slow_query_log() -> AResult<()> {
+    let ctx = get_server();
+    let parms = ctx.parms().with_slow_query_log_ms(1)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    cursor.execute(
+        "SELECT COUNT(*), 'a long string literal to redact' FROM sys.generate_series(0, 1000000)",
+    )?;
+    assert!(cursor.next_row()?);
+    assert_eq!(cursor.get_i64(0)?, Some(1000000));
+    Ok(())
+}
+
+#[test]
+fn test_query_timeout() -> AResult<()> {
+    let ctx = get_server();
+    let parms = ctx.parms().with_query_timeout(500)?;
+    let conn = Connection::new(parms)?;
+    let mut cursor = conn.cursor();
+    let result = cursor.execute(
+        "SELECT COUNT(*) FROM sys.generate_series(0, 1000000) a, sys.generate_series(0, 1000000) b",
+    );
+    assert_matches!(result, Err(CursorError::Server(_)));
+    Ok(())
+}
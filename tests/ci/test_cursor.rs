@@ -0,0 +1,526 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use claims::assert_matches;
+use monetdb::{parms::Value, sqlgen::in_list, CursorError, MonetType};
+
+use crate::{
+    context::{with_shared_cursor, with_shared_server},
+    get_server, AResult,
+};
+
+#[test]
+fn test_cancel_token() {
+    with_shared_server(|conn| {
+        let token = Arc::new(AtomicBool::new(false));
+        let mut cursor = conn.cursor().with_cancel_token(Arc::clone(&token));
+        cursor.execute("SELECT * FROM sys.generate_series(0, 1000)")?;
+        assert!(cursor.next_row()?);
+        assert!(cursor.next_row()?);
+
+        token.store(true, Ordering::SeqCst);
+        assert_matches!(cursor.next_row(), Err(CursorError::Cancelled));
+
+        token.store(false, Ordering::SeqCst);
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_i32(0)?, Some(2));
+        drop(cursor);
+        Ok(conn)
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_set_default_reply_size() -> AResult<()> {
+    let conn = get_server().connect()?;
+    conn.set_default_reply_size(7)?;
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT * FROM sys.generate_series(0, 100)")?;
+    let mut n = 0;
+    while cursor.next_row()? {
+        assert_eq!(cursor.get_i32(0)?, Some(n));
+        n += 1;
+    }
+    assert_eq!(n, 100);
+    Ok(())
+}
+
+#[test]
+fn test_unlimited_reply_size() -> AResult<()> {
+    let conn = get_server().connect()?;
+    conn.set_default_reply_size(0)?;
+    let mut cursor = conn.cursor();
+    cursor.execute("SELECT * FROM sys.generate_series(0, 5000)")?;
+    let values = cursor.collect_column::<i32>(0)?;
+    assert_eq!(values.len(), 5000);
+    assert_eq!(values.last(), Some(&Some(4999)));
+    Ok(())
+}
+
+#[test]
+fn test_describe() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute(
+            "DROP TABLE IF EXISTS describe_foo; CREATE TABLE describe_foo(i INT, v VARCHAR(10), d DECIMAL(8,3), ts TIMESTAMP(3))",
+        )?;
+        let columns = cursor.describe("SELECT i, v, d, ts FROM describe_foo WHERE i > ?;")?;
+        let names: Vec<_> = columns.iter().map(|c| c.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "describe_foo.i",
+                "describe_foo.v",
+                "describe_foo.d",
+                "describe_foo.ts"
+            ]
+        );
+        let types: Vec<_> = columns.iter().map(|c| *c.sql_type()).collect();
+        assert_eq!(
+            types,
+            [
+                MonetType::Int,
+                MonetType::Varchar(10),
+                MonetType::Decimal(8, 3),
+                MonetType::Timestamp(3)
+            ]
+        );
+        assert!(!cursor.has_result_set());
+        cursor.execute("DROP TABLE describe_foo")?;
+
+        cursor.execute("SELECT 1")?;
+        assert!(cursor.next_row()?);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_explain() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        let plan = cursor.explain("SELECT 42")?;
+        assert!(plan.lines().count() > 1, "{plan:?}");
+        assert!(plan.contains("function user."), "{plan:?}");
+        assert!(!cursor.has_result_set());
+        Ok(())
+    })
+}
+
+#[test]
+fn test_row_strings() -> AResult<()> {
+    with_shared_server(|conn| {
+        let mut cursor = conn.cursor();
+        assert_matches!(cursor.row_strings(), Err(CursorError::NoResultSet));
+
+        cursor.execute("SELECT 42, NULL, 'foo'")?;
+        assert!(cursor.next_row()?);
+        let row = cursor.row_strings()?;
+        assert_eq!(row, [Some("42".to_string()), None, Some("foo".to_string())]);
+        drop(cursor);
+        Ok(conn)
+    })
+}
+
+#[test]
+fn test_execute_many() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute(
+            "DROP TABLE IF EXISTS execute_many; CREATE TABLE execute_many(i INT, t TEXT)",
+        )?;
+        let rows: &[&[Value]] = &[
+            &[Value::Int(1), Value::from("one")],
+            &[Value::Int(2), Value::from("it's")],
+            &[Value::Int(3), Value::from(r"back\slash")],
+        ];
+        let n = cursor.execute_many("INSERT INTO execute_many(i, t) VALUES", rows)?;
+        assert_eq!(n, 3);
+
+        let nulls: &[&[Option<i32>]] = &[&[Some(4), None]];
+        let n = cursor.execute_many("INSERT INTO execute_many", nulls)?;
+        assert_eq!(n, 1);
+
+        // problems with the rows are detected before anything is sent
+        let ragged: &[&[i32]] = &[&[5, 5], &[6]];
+        let err = cursor
+            .execute_many("INSERT INTO execute_many", ragged)
+            .unwrap_err();
+        assert_matches!(
+            err,
+            CursorError::RowLength {
+                row: 1,
+                expected: 2,
+                actual: 1
+            }
+        );
+        let floats: &[&[f64]] = &[&[7.0, f64::NAN]];
+        let err = cursor
+            .execute_many("INSERT INTO execute_many", floats)
+            .unwrap_err();
+        assert_matches!(err, CursorError::Conversion { .. });
+
+        cursor.execute("SELECT t FROM execute_many ORDER BY i")?;
+        let mut texts = vec![];
+        while cursor.next_row()? {
+            texts.push(cursor.get::<String>(0)?);
+        }
+        assert_eq!(
+            texts,
+            [
+                Some("one".to_string()),
+                Some("it's".to_string()),
+                Some(r"back\slash".to_string()),
+                None
+            ]
+        );
+        cursor.execute("DROP TABLE execute_many")?;
+        Ok(())
+    })
+}
+
+#[test]
+fn test_last_error_detail() -> AResult<()> {
+    with_shared_server(|conn| {
+        let mut cursor = conn.cursor();
+        let Err(CursorError::Server(msg)) = cursor.execute("SELECT no_such_column") else {
+            panic!("expected a server error");
+        };
+        let detail = cursor.last_error_detail().unwrap();
+        assert!(detail.starts_with(&msg), "{detail:?} vs {msg:?}");
+
+        cursor.execute("SELECT 1")?;
+        assert_eq!(cursor.last_error_detail(), None);
+        drop(cursor);
+        Ok(conn)
+    })
+}
+
+#[test]
+fn test_execute_capped() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        // larger than the default reply size so more rows must be fetched
+        cursor.execute_capped("SELECT * FROM sys.generate_series(0, 1000)", 250)?;
+        let mut n = 0;
+        while cursor.next_row()? {
+            assert_eq!(cursor.get_i32(0)?, Some(n));
+            n += 1;
+        }
+        assert_eq!(n, 250);
+        assert!(!cursor.next_row()?);
+
+        // the cap does not carry over to the next execute
+        cursor.execute("SELECT * FROM sys.generate_series(0, 300)")?;
+        let mut n = 0;
+        while cursor.next_row()? {
+            n += 1;
+        }
+        assert_eq!(n, 300);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_collect_column() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        // larger than the default reply size so more rows must be fetched
+        cursor.execute("SELECT value, NULLIF(value % 2, 1) FROM sys.generate_series(0, 1000)")?;
+        let values: Vec<Option<i32>> = cursor.collect_column(1)?;
+        assert_eq!(values.len(), 1000);
+        assert_eq!(values[..3], [Some(0), None, Some(0)]);
+        assert!(!cursor.next_row()?);
+
+        cursor.execute("SELECT * FROM sys.generate_series(0, 10)")?;
+        assert!(cursor.next_row()?);
+        let values: Vec<Option<i64>> = cursor.collect_column(0)?;
+        assert_eq!(values, (1..10).map(Some).collect::<Vec<_>>());
+        assert_matches!(
+            cursor.collect_column::<i64>(1),
+            Err(CursorError::ColumnIndex {
+                index: 1,
+                columns: 1
+            })
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn test_collect_map() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute("SELECT * FROM (VALUES (1, 'one'), (2, NULL), (1, 'uno')) AS t(k, v)")?;
+        let map: HashMap<i32, Option<String>> = cursor.collect_map(0, 1)?;
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&1].as_deref(), Some("uno"));
+        assert_eq!(map[&2], None);
+
+        cursor.execute("SELECT * FROM (VALUES (1, 'one'), (2, NULL)) AS t(k, v)")?;
+        assert_matches!(
+            cursor.collect_map::<i32, String>(0, 1),
+            Err(CursorError::Conversion { .. })
+        );
+
+        cursor.execute("SELECT * FROM (VALUES (NULL, 'one')) AS t(k, v)")?;
+        assert_matches!(
+            cursor.collect_map::<i32, String>(0, 1),
+            Err(CursorError::Conversion { .. })
+        );
+
+        cursor.execute("SELECT 1, 2")?;
+        assert_matches!(
+            cursor.collect_map::<i32, i32>(0, 2),
+            Err(CursorError::ColumnIndex {
+                index: 2,
+                columns: 2
+            })
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn test_skip_rows() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        // larger than the default reply size so more rows must be fetched
+        cursor.execute("SELECT value, 'x' || value FROM sys.generate_series(0, 1000)")?;
+        assert_eq!(cursor.skip_rows(0)?, 0);
+        assert_eq!(cursor.skip_rows(500)?, 500);
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_i32(0)?, Some(500));
+        assert_eq!(cursor.get_str(1)?, Some("x500"));
+        assert_eq!(cursor.skip_rows(1000)?, 499);
+        assert!(!cursor.next_row()?);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_affected_then_rows() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute("DROP TABLE IF EXISTS returning_foo; CREATE TABLE returning_foo(id INT AUTO_INCREMENT, t TEXT)")?;
+        cursor.execute(
+            "INSERT INTO returning_foo(t) VALUES ('a'), ('b'), ('c');
+             SELECT id, t FROM returning_foo ORDER BY id",
+        )?;
+        assert_eq!(cursor.affected_then_rows()?, 3);
+        let mut ids = vec![];
+        while cursor.next_row()? {
+            ids.push(cursor.get_i32(0)?.unwrap());
+        }
+        assert_eq!(ids, [1, 2, 3]);
+
+        cursor.execute("SELECT 42")?;
+        assert_matches!(cursor.affected_then_rows(), Err(CursorError::NotAnUpdate));
+
+        cursor.execute("DELETE FROM returning_foo WHERE id = 1")?;
+        assert_matches!(cursor.affected_then_rows(), Err(CursorError::NoResultSet));
+        Ok(())
+    })
+}
+
+#[test]
+fn test_for_each_result_set() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute(
+            "DROP TABLE IF EXISTS for_each_foo; CREATE TABLE for_each_foo(i INT);
+             SELECT 1 AS a, 'one' AS b;
+             INSERT INTO for_each_foo VALUES (1), (2);
+             SELECT * FROM sys.generate_series(0, 1000);
+             SELECT i FROM for_each_foo ORDER BY i",
+        )?;
+        let mut seen = vec![];
+        cursor.for_each_result_set(|columns, rows| {
+            let names: Vec<_> = columns.iter().map(|c| c.name().to_string()).collect();
+            let mut values = vec![];
+            // stop early in the big one, the rest should be skipped
+            while let Some(row) = rows.next_row()? {
+                values.push(row.get_str(0)?.unwrap().to_string());
+                if values.len() == 3 {
+                    break;
+                }
+            }
+            seen.push((names.len(), values));
+            Ok(())
+        })?;
+        assert_eq!(
+            seen,
+            [
+                (2, vec!["1".to_string()]),
+                (1, vec!["0".to_string(), "1".to_string(), "2".to_string()]),
+                (1, vec!["1".to_string(), "2".to_string()]),
+            ]
+        );
+        assert!(!cursor.has_result_set());
+
+        // errors from the closure are passed on
+        cursor.execute("SELECT 1; SELECT 2")?;
+        let mut calls = 0;
+        let ret = cursor.for_each_result_set(|_, _| {
+            calls += 1;
+            Err(CursorError::NoResultSet)
+        });
+        assert_matches!(ret, Err(CursorError::NoResultSet));
+        assert_eq!(calls, 1);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_strict_result_sets() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        let sql = "DROP TABLE IF EXISTS strict_foo; CREATE TABLE strict_foo(i INT); SELECT 42";
+
+        cursor.execute(sql)?;
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_i32(0)?, Some(42));
+
+        cursor.set_strict_result_sets(true);
+        cursor.execute(sql)?;
+        assert_matches!(cursor.next_row(), Err(CursorError::NoResultSet));
+        assert!(cursor.next_reply()?);
+        assert_matches!(cursor.next_row(), Err(CursorError::NoResultSet));
+        assert!(cursor.next_reply()?);
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_i32(0)?, Some(42));
+        Ok(())
+    })
+}
+
+#[test]
+fn test_execute_fmt() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        let n = 42;
+        monetdb::execute!(cursor, "SELECT {n} + {}", 1)?;
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_i32(0)?, Some(43));
+        Ok(())
+    })
+}
+
+#[test]
+fn test_temporal_precision() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute(
+            "SELECT CAST('12:34:56' AS TIME(3)), CAST('2024-10-16 12:34:56' AS TIMESTAMP(6))",
+        )?;
+        let types: Vec<MonetType> = cursor
+            .column_metadata()
+            .iter()
+            .map(|c| *c.sql_type())
+            .collect();
+        assert_eq!(types, [MonetType::Time(3), MonetType::Timestamp(6)]);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_column_types() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute("SELECT 42, CAST('banana' AS VARCHAR(10)), CAST(1.5 AS DECIMAL(5, 2))")?;
+        assert_eq!(
+            cursor.column_types(),
+            [
+                MonetType::TinyInt,
+                MonetType::Varchar(10),
+                MonetType::Decimal(5, 2)
+            ]
+        );
+        cursor.execute("SET SCHEMA sys")?;
+        assert_eq!(cursor.column_types(), []);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_in_list() {
+    with_shared_cursor(|cursor| {
+        let values = ["a", "it's", "c"];
+        cursor.execute(&format!(
+            "SELECT s FROM (VALUES ('a'), ('b'), ('it''s')) AS t(s) WHERE s IN {} ORDER BY s",
+            in_list(&values)?
+        ))?;
+        let found = cursor.collect_column::<String>(0)?;
+        assert_eq!(found, [Some("a".to_string()), Some("it's".to_string())]);
+
+        cursor.execute(&format!(
+            "SELECT COUNT(*) FROM (VALUES (1), (2)) AS t(i) WHERE i IN {}",
+            in_list::<i32>(&[])?
+        ))?;
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_i64(0)?, Some(0));
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_get_row() {
+    with_shared_cursor(|cursor| {
+        cursor.execute("SELECT 42, 'forty-two', CAST(NULL AS DOUBLE)")?;
+        assert!(cursor.next_row()?);
+        let row: (i32, String, Option<f64>) = cursor.get_row()?;
+        assert_eq!(row, (42, "forty-two".to_string(), None));
+
+        // NULL requires Option
+        assert_matches!(
+            cursor.get_row::<(i32, String, f64)>(),
+            Err(CursorError::Conversion { .. })
+        );
+        // the number of columns must match
+        assert_matches!(
+            cursor.get_row::<(i32, String)>(),
+            Err(CursorError::ColumnCount {
+                expected: 2,
+                actual: 3
+            })
+        );
+
+        cursor.execute("SELECT 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12")?;
+        assert!(cursor.next_row()?);
+        type Twelve = (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8);
+        let row: Twelve = cursor.get_row()?;
+        assert_eq!(row, (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12));
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_interleaved_cursors() -> AResult<()> {
+    let conn = get_server().connect()?;
+    conn.set_default_reply_size(7)?;
+
+    let mut a = conn.cursor();
+    a.execute("SELECT * FROM sys.generate_series(0, 100)")?;
+    let mut values = vec![];
+    for _ in 0..10 {
+        assert!(a.next_row()?);
+        values.push(a.get_i32(0)?.unwrap());
+    }
+    assert!(a.is_streaming());
+
+    // run queries on another cursor, one read completely and one abandoned
+    // halfway, while A is in the middle of its result set
+    let mut b = conn.cursor();
+    b.execute("SELECT * FROM sys.generate_series(1000, 1020)")?;
+    let b_values = b.collect_column::<i32>(0)?;
+    assert_eq!(b_values.len(), 20);
+    b.execute("SELECT * FROM sys.generate_series(2000, 2100)")?;
+    assert!(b.next_row()?);
+    drop(b);
+
+    while a.next_row()? {
+        values.push(a.get_i32(0)?.unwrap());
+    }
+    assert_eq!(values, (0..100).collect::<Vec<_>>());
+    Ok(())
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0.  If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright 2024 MonetDB Foundation
+
+use claims::assert_matches;
+use monetdb::{CursorError, FromRow};
+
+use crate::{context::with_shared_cursor, AResult};
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Named {
+    i: i32,
+    #[monetdb(column = "t")]
+    text: String,
+    maybe: Option<i32>,
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Positional(i32, String, Option<i32>);
+
+#[test]
+fn test_derive_from_row() -> AResult<()> {
+    with_shared_cursor(|cursor| {
+        cursor.execute("SELECT 'forty-two' AS t, NULL AS maybe, 42 AS i")?;
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.column_index("i"), Some(2));
+        assert_eq!(cursor.column_index("nope"), None);
+        let row = Named::from_row(cursor)?;
+        assert_eq!(
+            row,
+            Named {
+                i: 42,
+                text: "forty-two".into(),
+                maybe: None,
+            }
+        );
+
+        cursor.execute("SELECT 42, 'forty-two', 43")?;
+        assert!(cursor.next_row()?);
+        let row = Positional::from_row(cursor)?;
+        assert_eq!(row, Positional(42, "forty-two".into(), Some(43)));
+
+        cursor.execute("SELECT NULL AS i, 'x' AS t, 1 AS maybe")?;
+        assert!(cursor.next_row()?);
+        assert_matches!(Named::from_row(cursor), Err(CursorError::Conversion { .. }));
+
+        cursor.execute("SELECT 1 AS i")?;
+        assert!(cursor.next_row()?);
+        assert_matches!(
+            Named::from_row(cursor),
+            Err(CursorError::UnknownColumn(c)) if c == "t"
+        );
+
+        Ok(())
+    })
+}
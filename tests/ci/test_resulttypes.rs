@@ -19,8 +19,9 @@ use monetdb::{
     convert::{
         raw_decimal::RawDecimal,
         raw_temporal::{RawDate, RawTime, RawTimeTz, RawTimestamp, RawTimestampTz},
-        FromMonet,
+        FromMonet, IntBool, ScaledDecimal,
     },
+    sqlgen::literal,
     Connection, Cursor, CursorResult, Parameters,
 };
 
@@ -57,6 +58,48 @@ fn test_varchar() {
     .unwrap()
 }
 
+#[test]
+fn test_char() {
+    check("CAST('x' AS CHAR(1))", 'x');
+    check("CAST('\u{e9}' AS CHAR(1))", '\u{e9}');
+
+    with_shared_cursor(|cursor| {
+        cursor.execute("SELECT CAST('xy' AS CHAR(2)), CAST(NULL AS CHAR(1))")?;
+        assert!(cursor.next_row()?);
+        assert!(cursor.get::<char>(0).is_err());
+        assert_eq!(cursor.get::<char>(1)?, None);
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_varchar_escapes() {
+    // Raw string literals keep the backslashes, the server escapes them and
+    // the other special characters when sending the result.
+    let values = [
+        r"back\slash\",
+        r#"\"quotes\" and 'apostrophes'"#,
+        "tab\tnewline\ncr\rformfeed\u{c}",
+        "bell\u{7}backspace\u{8}vtab\u{b}escape\u{1b}delete\u{7f}",
+        "next line\u{85}line separator\u{2028}",
+        "b\u{e4}nana \u{263A} \u{1F34C}",
+    ];
+    with_shared_cursor(|cursor| {
+        let columns = values
+            .iter()
+            .map(|v| literal(*v))
+            .collect::<Result<Vec<_>, _>>()?;
+        cursor.execute(&format!("SELECT {}", columns.join(", ")))?;
+        assert!(cursor.next_row()?);
+        for (i, expected) in values.iter().enumerate() {
+            assert_eq!(cursor.get_str(i)?, Some(*expected), "column {i}");
+        }
+        Ok(())
+    })
+    .unwrap()
+}
+
 #[test]
 fn test_ints() {
     for &value in &[0i8, 10, -10] {
@@ -108,6 +151,49 @@ fn test_ints() {
     }
 }
 
+#[test]
+fn test_int_bool() {
+    check("CAST(0 AS TINYINT)", IntBool(false));
+    check("CAST(1 AS TINYINT)", IntBool(true));
+    check("CAST(-3 AS INT)", IntBool(true));
+    check("true", IntBool(true));
+
+    with_shared_cursor(|cursor| {
+        cursor.execute("SELECT CAST(1 AS TINYINT), false, CAST(NULL AS SMALLINT)")?;
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_bool_lenient(0)?, Some(true));
+        assert_eq!(cursor.get_bool_lenient(1)?, Some(false));
+        assert_eq!(cursor.get_bool_lenient(2)?, None);
+        assert!(cursor.get_bool(0).is_err());
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_scaled_decimal() {
+    check(
+        "CAST(-12.5 AS DECIMAL(38, 10))",
+        ScaledDecimal(-125_000_000_000, 10),
+    );
+
+    with_shared_cursor(|cursor| {
+        cursor.execute(
+            "SELECT CAST('1234567890123456789012345678.0000000001' AS DECIMAL(38, 10)), \
+            CAST(NULL AS DECIMAL(38, 10)), CAST(42 AS INT)",
+        )?;
+        assert!(cursor.next_row()?);
+        assert_eq!(
+            cursor.get_scaled_decimal(0)?,
+            Some((12345678901234567890123456780000000001, 10))
+        );
+        assert_eq!(cursor.get_scaled_decimal(1)?, None);
+        assert!(cursor.get_scaled_decimal(2).is_err());
+        Ok(())
+    })
+    .unwrap();
+}
+
 #[test]
 fn test_blob() {
     check(r#" BLOB '414243' "#, Vec::from("ABC"));
@@ -123,6 +209,14 @@ fn test_uuid() {
     check(r#"  UUID '7B4DCDD0E0F24D05A81B599F445843B6'  "#, u);
 }
 
+#[test]
+#[cfg(feature = "uuid")]
+fn test_uuid_roundtrip() {
+    let u = uuid::Uuid::parse_str("7b4dcdd0-e0f2-4d05-a81b-599f445843b6").unwrap();
+    check(&literal(&u).unwrap(), u);
+    check(&literal(&Some(u)).unwrap(), u);
+}
+
 #[test]
 fn test_rawdecimal() {
     check("CAST( 12.34 AS DECIMAL(7,3))", RawDecimal(12340i32, 3));
@@ -145,6 +239,28 @@ fn test_decimal_as_float() {
     check("CAST( -12.34 AS DECIMAL(7,0))", -12.0f64);
 }
 
+#[test]
+fn test_decimal_string() {
+    with_shared_cursor(|cursor| {
+        cursor.execute(
+            "SELECT CAST(-12.34 AS DECIMAL(7,3)), \
+            CAST('12345678901234567890.123456789012345678' AS DECIMAL(38,18)), \
+            CAST(NULL AS DECIMAL(5,2)), 12.5e0, 'hello'",
+        )?;
+        assert!(cursor.next_row()?);
+        assert_eq!(cursor.get_decimal_string(0)?.unwrap(), "-12.340");
+        assert_eq!(
+            cursor.get_decimal_string(1)?.unwrap(),
+            "12345678901234567890.123456789012345678"
+        );
+        assert_eq!(cursor.get_decimal_string(2)?, None);
+        assert!(cursor.get_decimal_string(3).is_err());
+        assert!(cursor.get_decimal_string(4).is_err());
+        Ok(())
+    })
+    .unwrap();
+}
+
 #[cfg(feature = "rust_decimal")]
 #[test]
 fn test_rust_decimal() {